@@ -0,0 +1,107 @@
+use actix_web::http::Method;
+use std::ops::BitOr;
+
+/// One of the actions that a [`RestModel`](crate::RestModel) exposes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// `GET` the collection.
+    List,
+    /// `GET` a single row.
+    Retrieve,
+    /// `POST` a new row.
+    Create,
+    /// `PATCH` a row.
+    Update,
+    /// `PUT` a row, creating it if it does not exist.
+    Replace,
+    /// `DELETE` a row.
+    Delete,
+    /// An action added with [`RestModel::detail_action`](crate::RestModel::detail_action)
+    /// or [`RestModel::collection_action`](crate::RestModel::collection_action), by name.
+    Custom(&'static str),
+}
+
+impl Action {
+    /// The method that a built-in action is requested with.
+    pub(crate) fn method(self) -> Option<Method> {
+        let method = match self {
+            Self::List | Self::Retrieve => Method::GET,
+            Self::Create => Method::POST,
+            Self::Update => Method::PATCH,
+            Self::Replace => Method::PUT,
+            Self::Delete => Method::DELETE,
+            Self::Custom(..) => return None,
+        };
+        Some(method)
+    }
+
+    const fn bit(self) -> u8 {
+        match self {
+            Self::List => 1,
+            Self::Retrieve => 1 << 1,
+            Self::Create => 1 << 2,
+            Self::Update => 1 << 3,
+            Self::Replace => 1 << 4,
+            Self::Delete => 1 << 5,
+            Self::Custom(..) => 0,
+        }
+    }
+}
+
+/// A set of the built-in [`Action`]s, such as those that a [`RestModel`](crate::RestModel)
+/// exposes. Custom actions are exposed once they are added, and never part of a set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Actions(u8);
+
+impl Actions {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self::NONE
+        .with(Action::List)
+        .with(Action::Retrieve)
+        .with(Action::Create)
+        .with(Action::Update)
+        .with(Action::Replace)
+        .with(Action::Delete);
+    /// The actions that only read rows.
+    pub const READ_ONLY: Self = Self::NONE.with(Action::List).with(Action::Retrieve);
+
+    pub const fn contains(self, action: Action) -> bool {
+        self.0 & action.bit() != 0
+    }
+
+    pub const fn with(self, action: Action) -> Self {
+        Self(self.0 | action.bit())
+    }
+
+    pub const fn without(self, action: Action) -> Self {
+        Self(self.0 & !action.bit())
+    }
+}
+
+impl Default for Actions {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl From<Action> for Actions {
+    fn from(action: Action) -> Self {
+        Self::NONE.with(action)
+    }
+}
+
+impl BitOr<Action> for Actions {
+    type Output = Self;
+
+    fn bitor(self, action: Action) -> Self {
+        self.with(action)
+    }
+}
+
+impl BitOr for Action {
+    type Output = Actions;
+
+    fn bitor(self, action: Action) -> Actions {
+        Actions::from(self).with(action)
+    }
+}
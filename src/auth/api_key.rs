@@ -0,0 +1,78 @@
+use super::{credentials, database, primary_key, Authenticate};
+use crate::error::Unauthorized;
+use actix_web::HttpRequest;
+use async_trait::async_trait;
+use ring::digest;
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter, Related};
+use std::marker::PhantomData;
+
+/// Authenticates bearer tokens against the API keys stored in the table of `K`, each of
+/// which belongs to a user of `U`.
+pub struct ApiKeyAuth<K: EntityTrait, U> {
+    key: K::Column,
+    hashed: bool,
+    _pd: PhantomData<U>,
+}
+
+impl<K: EntityTrait, U> ApiKeyAuth<K, U> {
+    /// Finds keys by the value of the `key` column.
+    pub fn new(key: K::Column) -> Self {
+        Self {
+            key,
+            hashed: false,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Finds keys by their SHA-256 digest, in lowercase hex, so that the keys themselves
+    /// need not be stored.
+    pub fn hashed(mut self) -> Self {
+        self.hashed = true;
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl<K, U> Authenticate for ApiKeyAuth<K, U>
+where
+    K: EntityTrait + Related<U>,
+    U: EntityTrait,
+{
+    type User = U::Model;
+
+    async fn authenticate(&self, request: &HttpRequest) -> crate::Result<Option<U::Model>> {
+        let token = match credentials(request, "Bearer") {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        let db = database(request)?;
+        let value = if self.hashed {
+            digest::digest(&digest::SHA256, token.as_bytes())
+                .as_ref()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect()
+        } else {
+            token.to_owned()
+        };
+        let key = K::find()
+            .filter(self.key.eq(value))
+            .one(db)
+            .await?
+            .ok_or_else(|| Unauthorized("Invalid API key"))?;
+        let user = key
+            .find_related(U::default())
+            .one(db)
+            .await?
+            .ok_or_else(|| Unauthorized("Invalid API key"))?;
+        Ok(Some(user))
+    }
+
+    fn user_id(&self, user: &U::Model) -> Option<String> {
+        primary_key(user)
+    }
+
+    fn challenge(&self) -> String {
+        "Bearer".to_owned()
+    }
+}
@@ -0,0 +1,69 @@
+use super::{credentials, database, primary_key, Authenticate};
+use crate::error::Unauthorized;
+use actix_web::HttpRequest;
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+type Verify<M> = Box<dyn Fn(&M, &str) -> bool>;
+
+/// Authenticates HTTP Basic credentials against the rows of the user entity `E`, found
+/// by their username.
+///
+/// How passwords are hashed is up to the app, so each is checked by `verify`, given the
+/// row with the username and the password that was sent.
+pub struct BasicAuth<E: EntityTrait> {
+    username: E::Column,
+    verify: Verify<E::Model>,
+    realm: String,
+}
+
+impl<E: EntityTrait> BasicAuth<E> {
+    pub fn new(username: E::Column, verify: impl Fn(&E::Model, &str) -> bool + 'static) -> Self {
+        Self {
+            username,
+            verify: Box::new(verify),
+            realm: "api".to_owned(),
+        }
+    }
+
+    /// The realm named in challenges, `api` by default.
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = realm.into();
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl<E: EntityTrait> Authenticate for BasicAuth<E> {
+    type User = E::Model;
+
+    async fn authenticate(&self, request: &HttpRequest) -> crate::Result<Option<E::Model>> {
+        let credentials = match credentials(request, "Basic") {
+            Some(credentials) => credentials,
+            None => return Ok(None),
+        };
+        let (username, password) = base64::decode(credentials)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .and_then(|decoded| {
+                let (username, password) = decoded.split_once(':')?;
+                Some((username.to_owned(), password.to_owned()))
+            })
+            .ok_or_else(|| Unauthorized("Malformed Basic credentials"))?;
+        let user = E::find()
+            .filter(self.username.eq(username))
+            .one(database(request)?)
+            .await?
+            .filter(|user| (self.verify)(user, &password))
+            .ok_or_else(|| Unauthorized("Invalid username or password"))?;
+        Ok(Some(user))
+    }
+
+    fn user_id(&self, user: &E::Model) -> Option<String> {
+        primary_key(user)
+    }
+
+    fn challenge(&self) -> String {
+        format!("Basic realm=\"{}\"", self.realm)
+    }
+}
@@ -0,0 +1,138 @@
+use super::{credentials, Authenticate};
+use crate::error::Unauthorized;
+use actix_web::HttpRequest;
+use async_trait::async_trait;
+use ring::{hmac, signature};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value as Json;
+use std::marker::PhantomData;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+enum Key {
+    Hs256(hmac::Key),
+    /// A DER-encoded `RSAPublicKey`.
+    Rs256(Vec<u8>),
+}
+
+/// Authenticates bearer JSON Web Tokens signed with a locally configured key, yielding
+/// their claims `C` as the user.
+///
+/// Tokens must be signed with the configured algorithm, and are rejected once their `exp`
+/// has passed or before their `nbf`. Bearer tokens that are not shaped like a JWT are left
+/// to other backends.
+pub struct JwtAuth<C> {
+    key: Key,
+    _pd: PhantomData<C>,
+}
+
+impl<C> JwtAuth<C> {
+    /// Verifies tokens signed with HMAC SHA-256 using `secret`.
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self {
+            key: Key::Hs256(hmac::Key::new(hmac::HMAC_SHA256, secret)),
+            _pd: PhantomData,
+        }
+    }
+
+    /// Verifies tokens signed with RSA SHA-256, given the DER-encoded `RSAPublicKey`, such
+    /// as the output of `openssl rsa -pubin -RSAPublicKey_out -outform DER`.
+    pub fn rs256(public_key: &[u8]) -> Self {
+        Self {
+            key: Key::Rs256(public_key.to_owned()),
+            _pd: PhantomData,
+        }
+    }
+
+    fn algorithm(&self) -> &'static str {
+        match self.key {
+            Key::Hs256(..) => "HS256",
+            Key::Rs256(..) => "RS256",
+        }
+    }
+
+    fn verify_signature(&self, message: &[u8], signature: &[u8]) -> bool {
+        match &self.key {
+            Key::Hs256(key) => hmac::verify(key, message, signature).is_ok(),
+            Key::Rs256(key) => {
+                signature::UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, key)
+                    .verify(message, signature)
+                    .is_ok()
+            }
+        }
+    }
+}
+
+impl<C: DeserializeOwned> JwtAuth<C> {
+    fn verify(&self, token: &str) -> crate::Result<C> {
+        let mut parts = token.split('.');
+        let (header, claims, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(header), Some(claims), Some(signature)) => (header, claims, signature),
+            _ => return Err(Unauthorized("Malformed token")),
+        };
+        let decode = |part: &str| {
+            base64::decode_config(part, base64::URL_SAFE_NO_PAD)
+                .map_err(|_| Unauthorized("Malformed token"))
+        };
+        let parse = |part: &str| -> crate::Result<Json> {
+            serde_json::from_slice(&decode(part)?).map_err(|_| Unauthorized("Malformed token"))
+        };
+
+        if parse(header)?.get("alg").and_then(Json::as_str) != Some(self.algorithm()) {
+            return Err(Unauthorized("Unexpected token algorithm"));
+        }
+        let message = &token[..header.len() + 1 + claims.len()];
+        if !self.verify_signature(message.as_bytes(), &decode(signature)?) {
+            return Err(Unauthorized("Invalid token signature"));
+        }
+
+        let claims = parse(claims)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs_f64())
+            .unwrap_or_default();
+        // A NumericDate may have a fraction, but a claim that is not a number at all
+        // cannot be checked, so the token is refused rather than never expiring.
+        let time = |name: &str| match claims.get(name) {
+            Some(time) => time
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| Unauthorized(format!("Invalid {name} claim"))),
+            None => Ok(None),
+        };
+        if time("exp")?.is_some_and(|exp| exp <= now) {
+            return Err(Unauthorized("Token has expired"));
+        }
+        if time("nbf")?.is_some_and(|nbf| nbf > now) {
+            return Err(Unauthorized("Token is not valid yet"));
+        }
+        serde_json::from_value(claims).map_err(|_| Unauthorized("Invalid token claims"))
+    }
+}
+
+#[async_trait(?Send)]
+impl<C> Authenticate for JwtAuth<C>
+where
+    C: Serialize + DeserializeOwned + Clone + 'static,
+{
+    type User = C;
+
+    async fn authenticate(&self, request: &HttpRequest) -> crate::Result<Option<C>> {
+        match credentials(request, "Bearer") {
+            Some(token) if token.split('.').count() == 3 => self.verify(token).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn challenge(&self) -> String {
+        "Bearer".to_owned()
+    }
+
+    /// The `sub` claim.
+    fn user_id(&self, user: &C) -> Option<String> {
+        match serde_json::to_value(user).ok()?.get("sub")? {
+            Json::String(sub) => Some(sub.clone()),
+            Json::Number(sub) => Some(sub.to_string()),
+            _ => None,
+        }
+    }
+}
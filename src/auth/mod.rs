@@ -0,0 +1,96 @@
+//! Authentication of the user making a request.
+//!
+//! Wrap an app or scope in [`Authentication`](crate::Authentication) with one of the
+//! backends here, then read the user with the [`CurrentUser`](crate::CurrentUser)
+//! extractor, or from the [`Context`](crate::Context) given to hooks.
+
+mod api_key;
+mod basic;
+mod jwt;
+
+pub use api_key::ApiKeyAuth;
+pub use basic::BasicAuth;
+pub use jwt::JwtAuth;
+
+use crate::error::MissingDatabase;
+use crate::value;
+use actix_web::{http::header, web, HttpRequest};
+use async_trait::async_trait;
+use sea_orm::{DatabaseConnection, EntityTrait, Iterable, ModelTrait, PrimaryKeyToColumn};
+
+/// A way of identifying the user making a request from the credentials it carries.
+#[async_trait(?Send)]
+pub trait Authenticate {
+    type User: Clone + 'static;
+
+    /// Identifies the user whose credentials the request carries. A request without any
+    /// credentials that this backend understands is anonymous, and yields `None`, while
+    /// invalid credentials fail the request.
+    ///
+    /// Backends that look users up in the database read the `DatabaseConnection` from the
+    /// app data, so that it is only required once a request carries credentials.
+    async fn authenticate(&self, request: &HttpRequest) -> crate::Result<Option<Self::User>>;
+
+    /// The `WWW-Authenticate` challenge sent with responses that require a user.
+    fn challenge(&self) -> String;
+
+    /// The id that rows owned by `user` refer to them by, for permissions such as
+    /// [`IsOwner`](crate::IsOwner).
+    fn user_id(&self, _user: &Self::User) -> Option<String> {
+        None
+    }
+}
+
+/// Tries `A`, then `B` if the request carries no credentials for `A`.
+#[async_trait(?Send)]
+impl<A, B> Authenticate for (A, B)
+where
+    A: Authenticate,
+    B: Authenticate<User = A::User>,
+{
+    type User = A::User;
+
+    async fn authenticate(&self, request: &HttpRequest) -> crate::Result<Option<Self::User>> {
+        match self.0.authenticate(request).await? {
+            Some(user) => Ok(Some(user)),
+            None => self.1.authenticate(request).await,
+        }
+    }
+
+    fn challenge(&self) -> String {
+        format!("{}, {}", self.0.challenge(), self.1.challenge())
+    }
+
+    fn user_id(&self, user: &Self::User) -> Option<String> {
+        self.0.user_id(user).or_else(|| self.1.user_id(user))
+    }
+}
+
+/// The id of a user stored as a row, which is its primary key.
+pub(crate) fn primary_key<M: ModelTrait>(user: &M) -> Option<String> {
+    let column = <M::Entity as EntityTrait>::PrimaryKey::iter()
+        .next()?
+        .into_column();
+    value::format(&user.get(column))
+}
+
+/// The `DatabaseConnection` in the app data, which backends that look users up need.
+pub(crate) fn database(request: &HttpRequest) -> crate::Result<&DatabaseConnection> {
+    request
+        .app_data::<web::Data<DatabaseConnection>>()
+        .map(|db| &***db)
+        .ok_or_else(MissingDatabase)
+}
+
+/// The credentials of the `Authorization` header, if it uses `scheme`.
+pub(crate) fn credentials<'a>(request: &'a HttpRequest, scheme: &str) -> Option<&'a str> {
+    let value = request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?;
+    let (given, credentials) = value.split_once(' ')?;
+    given
+        .eq_ignore_ascii_case(scheme)
+        .then(|| credentials.trim())
+}
@@ -0,0 +1,10 @@
+use sea_orm::prelude::Uuid;
+use serde::{Deserialize, Serialize};
+
+/// The claims of the tokens that clients authenticate with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// The id of the user the token was issued to.
+    pub sub: Uuid,
+    pub exp: u64,
+}
@@ -1,7 +1,5 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.7.0
 
-pub mod prelude;
-
 pub mod comments;
 pub mod posts;
 pub mod users;
@@ -1,8 +1,11 @@
-use actix_web::{web, App, HttpServer};
+use actix_web::{http::Method, web, App, HttpServer};
+use anyhow::Context;
 use sea_orm::Database;
 
-use woof::RestModel;
+use woof::auth::JwtAuth;
+use woof::{Actions, Authentication, CursorKey, RestModel};
 
+mod auth;
 mod entity;
 mod model;
 
@@ -10,16 +13,41 @@ mod model;
 async fn main() -> anyhow::Result<()> {
     dotenv::dotenv()?;
     pretty_env_logger::init();
-    let db = Database::connect(std::env::var("DATABASE_URL").unwrap()).await?;
+    let db = web::Data::new(Database::connect(std::env::var("DATABASE_URL").unwrap()).await?);
+    let cursor_key = std::env::var("CURSOR_KEY")
+        .context("CURSOR_KEY must be set to the secret that signs pagination cursors")?;
+    let cursor_key = web::Data::new(CursorKey::new(cursor_key.as_bytes()));
+    let jwt_secret = std::env::var("JWT_SECRET")
+        .context("JWT_SECRET must be set to the secret that signs access tokens")?;
 
     HttpServer::new(move || {
         App::new()
+            .wrap(Authentication::new(JwtAuth::<auth::Claims>::hs256(
+                jwt_secret.as_bytes(),
+            )))
             .wrap(actix_web::middleware::NormalizePath::trim())
-            .app_data(web::Data::new(db.clone()))
-            .service(RestModel::<model::users::RestModel>::new("/users").as_service())
-            .service(RestModel::<model::posts::RestModel>::new("/posts").as_service())
+            .app_data(db.clone())
+            .app_data(cursor_key.clone())
+            .service(
+                RestModel::<model::users::RestModel>::new("/users")
+                    .collection_action(Method::GET, "me", model::users::me)
+                    .related::<model::posts::RestModel>()
+                    .related::<model::comments::RestModel>()
+                    .related::<model::votes::RestModel>()
+                    .as_service(),
+            )
+            .service(
+                RestModel::<model::posts::RestModel>::new("/posts")
+                    .nest(RestModel::<model::comments::RestModel>::new("/comments"))
+                    .nest(RestModel::<model::votes::RestModel>::new("/votes"))
+                    .as_service(),
+            )
             .service(RestModel::<model::comments::RestModel>::new("/comments").as_service())
-            .service(RestModel::<model::votes::RestModel>::new("/votes").as_service())
+            .service(
+                RestModel::<model::votes::RestModel>::new("/votes")
+                    .actions(Actions::READ_ONLY)
+                    .as_service(),
+            )
     })
     .bind(("0.0.0.0", 8080))?
     .run()
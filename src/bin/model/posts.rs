@@ -1,100 +1,64 @@
+use crate::auth::Claims;
 use crate::entity::posts::*;
-use actix_web::dev::{Path, Url};
+use async_trait::async_trait;
 use sea_orm::entity::prelude::*;
-use sea_orm::entity::{ActiveValue, IntoActiveModel};
-use sea_orm::Condition;
+use sea_orm::ActiveValue::Set;
 use serde::{Deserialize, Serialize};
-use woof::{Create, Filter, Rest, Update};
+use woof::{Context, Create, CurrentUser, Filter, Hooks, Rest, Update};
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveIntoActiveModel)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Create)]
 pub struct CreateModel {
     pub title: String,
     pub content: String,
-    pub author: Uuid,
 }
 
-impl Create<ActiveModel> for CreateModel {}
-
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Update)]
 pub struct UpdateModel {
     pub title: Option<String>,
     pub content: Option<String>,
 }
 
-impl IntoActiveModel<ActiveModel> for UpdateModel {
-    fn into_active_model(self) -> ActiveModel {
-        let mut active_model = <ActiveModel as ActiveModelTrait>::default();
-        if let Some(title) = self.title {
-            active_model.title = ActiveValue::Set(title);
-        }
-        if let Some(content) = self.content {
-            active_model.content = ActiveValue::Set(content);
-        }
-        active_model
-    }
-}
-
-impl Update<ActiveModel> for UpdateModel {}
-
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Filter)]
 pub struct FilterModel {
     limit: Option<usize>,
     offset: Option<usize>,
     page: Option<usize>,
     cursor: Option<String>,
+    ordering: Option<String>,
     title: Option<String>,
     author: Option<Uuid>,
 }
 
-impl Filter for FilterModel {
-    fn limit(&self) -> usize {
-        self.limit.unwrap_or(20)
-    }
-
-    fn offset(&self) -> usize {
-        self.limit.unwrap_or(0)
-    }
+#[derive(Rest)]
+#[woof(
+    pagination = "woof::CursorPagination<Model>",
+    orderable = "title",
+    expand = "author: super::users::RestModel, comments: super::comments::RestModel",
+    expand_depth = "2",
+    permission = "woof::Or(woof::ReadOnly, woof::And(woof::IsAuthenticated, woof::IsOwner(Column::Author)))",
+    hooks
+)]
+pub struct RestModel;
 
-    fn page(&self) -> usize {
-        self.limit.unwrap_or(0)
+#[async_trait(?Send)]
+impl Hooks for RestModel {
+    async fn before_create(ctx: &Context<'_>, active_model: &mut ActiveModel) -> woof::Result<()> {
+        active_model.author = Set(CurrentUser::<Claims>::from_context(ctx)?.sub);
+        Ok(())
     }
 
-    fn cursor(&self) -> Option<&str> {
-        self.cursor.as_deref()
+    async fn before_replace(ctx: &Context<'_>, active_model: &mut ActiveModel) -> woof::Result<()> {
+        active_model.author = Set(CurrentUser::<Claims>::from_context(ctx)?.sub);
+        Ok(())
     }
 
-    fn condition(&self) -> Condition {
-        let mut condition = Condition::all();
-        if let Some(title) = &self.title {
-            condition = condition.add(Column::Title.eq(title.to_owned()));
-        }
-        if let Some(author) = self.author {
-            condition = condition.add(Column::Author.eq(author));
-        }
-        condition
+    async fn after_create(_ctx: &Context<'_>, model: &Model) -> woof::Result<()> {
+        log::info!("Post {} created by {}", model.id, model.author);
+        Ok(())
     }
-}
-
-pub struct RestModel;
-impl Rest for RestModel {
-    type Entity = Entity;
-    type Repr = Model;
-    type ActiveModel = ActiveModel;
-    type Update = UpdateModel;
-    type Create = CreateModel;
-    type Filter = FilterModel;
 
-    fn id_from_path(
-        scope: Option<&str>,
-        path: &Path<Url>,
-    ) -> woof::Result<<PrimaryKey as PrimaryKeyTrait>::ValueType> {
-        let scope = scope.map(|scope| format!("{scope}_")).unwrap_or_default();
-        let id_path = scope + "id";
-        let id = path
-            .get(&id_path)
-            .ok_or_else(|| woof::error::MissingPathSegment(&id_path))?
-            .parse()
-            .map_err(|_| woof::error::InvalidPathSegment(&id_path))?;
-        Ok(id)
+    async fn after_delete(_ctx: &Context<'_>, active_model: &ActiveModel) -> woof::Result<()> {
+        log::info!("Post {:?} deleted", active_model.id);
+        Ok(())
     }
 }
@@ -1,99 +1,114 @@
+use crate::auth::Claims;
 use crate::entity::users::*;
-use actix_web::dev::{Path, Url};
-use sea_orm::entity::prelude::*;
-use sea_orm::entity::{ActiveValue, IntoActiveModel};
-use sea_orm::{Condition, PrimaryKeyTrait};
+use actix_web::web;
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
-use woof::{Create, Filter, Rest, Update};
+use woof::{Create, CurrentUser, Filter, Rest, Update, Validate, ValidationErrors};
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveIntoActiveModel)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Create)]
+#[woof(validate)]
 pub struct CreateModel {
     pub username: String,
     pub email: String,
 }
 
-impl Create<ActiveModel> for CreateModel {}
-
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct UpdateModel {
-    pub username: Option<String>,
-    pub email: Option<String>,
-}
+#[async_trait(?Send)]
+impl Validate for CreateModel {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_username(&self.username, errors);
+        validate_email(&self.email, errors);
+    }
 
-impl IntoActiveModel<ActiveModel> for UpdateModel {
-    fn into_active_model(self) -> ActiveModel {
-        let mut active_model = <ActiveModel as ActiveModelTrait>::default();
-        if let Some(username) = self.username {
-            active_model.username = ActiveValue::Set(username);
+    async fn validate_with(
+        &self,
+        db: &DatabaseConnection,
+        errors: &mut ValidationErrors,
+    ) -> woof::Result<()> {
+        // Only worth a query if the username is otherwise valid.
+        if errors.field("username").is_empty() {
+            validate_unique_username(&self.username, db, errors).await?;
         }
-        if let Some(email) = self.email {
-            active_model.email = ActiveValue::Set(email);
-        }
-        active_model
+        Ok(())
     }
 }
 
-impl Update<ActiveModel> for UpdateModel {}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Update)]
+#[woof(validate)]
+pub struct UpdateModel {
+    pub username: Option<String>,
+    pub email: Option<String>,
+}
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Filter)]
 pub struct FilterModel {
     limit: Option<usize>,
     offset: Option<usize>,
     page: Option<usize>,
     cursor: Option<String>,
+    ordering: Option<String>,
     email: Option<String>,
     username: Option<String>,
 }
 
-impl Filter for FilterModel {
-    fn limit(&self) -> usize {
-        self.limit.unwrap_or(20)
+#[async_trait(?Send)]
+impl Validate for UpdateModel {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(username) = &self.username {
+            validate_username(username, errors);
+        }
+        if let Some(email) = &self.email {
+            validate_email(email, errors);
+        }
     }
+}
 
-    fn offset(&self) -> usize {
-        self.limit.unwrap_or(0)
+fn validate_username(username: &str, errors: &mut ValidationErrors) {
+    if username.is_empty() {
+        errors.add("username", "may not be empty");
     }
-
-    fn page(&self) -> usize {
-        self.limit.unwrap_or(0)
+    if username.chars().count() > 32 {
+        errors.add("username", "may not be longer than 32 characters");
     }
+}
 
-    fn cursor(&self) -> Option<&str> {
-        self.cursor.as_deref()
+fn validate_email(email: &str, errors: &mut ValidationErrors) {
+    let valid = email
+        .split_once('@')
+        .filter(|(local, domain)| !local.is_empty() && domain.contains('.'))
+        .is_some();
+    if !valid {
+        errors.add("email", "is not a valid email address");
     }
+}
 
-    fn condition(&self) -> Condition {
-        let mut condition = Condition::all();
-        if let Some(email) = &self.email {
-            condition = condition.add(Column::Email.eq(email.to_owned()));
-        }
-        if let Some(username) = &self.username {
-            condition = condition.add(Column::Username.eq(username.to_owned()));
-        }
-        condition
+async fn validate_unique_username(
+    username: &str,
+    db: &DatabaseConnection,
+    errors: &mut ValidationErrors,
+) -> woof::Result<()> {
+    let taken = Entity::find()
+        .filter(Column::Username.eq(username))
+        .count(db)
+        .await?;
+    if taken > 0 {
+        errors.add("username", "is already taken");
     }
+    Ok(())
 }
 
+#[derive(Rest)]
+#[woof(orderable = "username, email, created_at", ordering = "-created_at")]
 pub struct RestModel;
-impl Rest for RestModel {
-    type Entity = Entity;
-    type Repr = Model;
-    type ActiveModel = ActiveModel;
-    type Create = CreateModel;
-    type Update = UpdateModel;
-    type Filter = FilterModel;
 
-    fn id_from_path(
-        scope: Option<&str>,
-        path: &Path<Url>,
-    ) -> woof::Result<<PrimaryKey as PrimaryKeyTrait>::ValueType> {
-        let scope = scope.map(|scope| format!("{scope}_")).unwrap_or_default();
-        let id_path = scope + "id";
-        let id = path
-            .get(&id_path)
-            .ok_or_else(|| woof::error::MissingPathSegment(&id_path))?
-            .parse()
-            .map_err(|_| woof::error::InvalidPathSegment(&id_path))?;
-        Ok(id)
-    }
+/// `GET /users/me`, the user that made the request.
+pub async fn me(
+    user: CurrentUser<Claims>,
+    db: web::Data<DatabaseConnection>,
+) -> woof::Result<web::Json<Model>> {
+    let me = Entity::find_by_id(user.sub)
+        .one(&**db)
+        .await?
+        .ok_or_else(woof::error::NotFound)?;
+    Ok(web::Json(me))
 }
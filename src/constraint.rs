@@ -0,0 +1,335 @@
+use crate::error::Problem;
+use actix_web::http::StatusCode;
+use sea_orm::{DbErr, EntityTrait, IdenStatic, Iterable};
+use std::fmt::{self, Display, Formatter};
+
+/// A database constraint that a write violated, which is the client's fault rather than
+/// the server's.
+///
+/// Violations are told apart by their SQLSTATE. sea-orm reports database errors only as
+/// text, so the SQLSTATE is guessed from the wording of English Postgres messages, which
+/// Postgres only sends with `lc_messages = 'C'` or another English locale. An error that
+/// is not recognized remains a server error, and is logged as a warning if it may have
+/// been a violation after all.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConstraintViolation {
+    /// `23505`: another row already has the same value in a unique column.
+    Unique {
+        constraint: String,
+        column: Option<String>,
+    },
+    /// `23503`: a foreign key refers to a row that does not exist.
+    ForeignKey {
+        constraint: String,
+        column: Option<String>,
+    },
+    /// `23503`: a row cannot be deleted because other rows still refer to it.
+    Referenced { constraint: String },
+    /// `23502`: a column that may not be null was left null.
+    NotNull { column: String },
+    /// `23514`: a row failed a check constraint.
+    Check { constraint: String },
+    /// `22P02` or `22001`: a value could not be stored in a column of its type, such as
+    /// a malformed UUID or a string that is too long.
+    InvalidValue,
+}
+
+impl ConstraintViolation {
+    /// Recognizes the constraint violated by `error`, if any, warning about database
+    /// errors that are not recognized.
+    pub(crate) fn from_error(error: &DbErr) -> Option<Self> {
+        let message = error.to_string();
+        let violation = Self::parse(&message, None, &[]);
+        if violation.is_none() && may_be_violation(error) {
+            log::warn!(
+                "{message} was not recognized as a constraint violation, which requires \
+                 Postgres to send its messages in English"
+            );
+        }
+        violation
+    }
+
+    /// Recognizes the constraint violated by a database error, if any.
+    ///
+    /// Postgres names constraints `{table}_{column}_key` and `{table}_{column}_fkey` by
+    /// default. When the table is known, and one of `columns` is named like that, the
+    /// violation names that column.
+    pub(crate) fn parse(message: &str, table: Option<&str>, columns: &[&str]) -> Option<Self> {
+        let column = |constraint: &str, table: Option<&str>, suffix: &str| {
+            let column = constraint
+                .strip_prefix(table?)?
+                .strip_prefix('_')?
+                .strip_suffix(suffix)?;
+            columns
+                .iter()
+                .find(|name| **name == column)
+                .map(|name| name.to_string())
+        };
+
+        // The names in each message come in the same order in every translation, so only
+        // the SQLSTATE depends on the wording.
+        let names = quoted(message);
+        let name = |index: usize| names.get(index).map(|name| name.to_string());
+        match sqlstate(message)? {
+            "23505" => {
+                let constraint = name(0)?;
+                Some(Self::Unique {
+                    column: column(&constraint, table, "_key"),
+                    constraint,
+                })
+            }
+            // `update or delete on table "{table}" violates foreign key constraint
+            // "{constraint}" on table "{referencing}"`
+            "23503" if names.len() > 2 => Some(Self::Referenced {
+                constraint: name(1)?,
+            }),
+            // `insert or update on table "{table}" violates foreign key constraint
+            // "{constraint}"`
+            "23503" => {
+                let constraint = name(1)?;
+                Some(Self::ForeignKey {
+                    column: column(&constraint, names.first().copied().or(table), "_fkey"),
+                    constraint,
+                })
+            }
+            "23502" => Some(Self::NotNull { column: name(0)? }),
+            // `new row for relation "{table}" violates check constraint "{constraint}"`
+            "23514" => Some(Self::Check {
+                constraint: name(names.len().checked_sub(1)?)?,
+            }),
+            "22P02" | "22001" => Some(Self::InvalidValue),
+            _ => None,
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Unique { .. } | Self::Referenced { .. } => StatusCode::CONFLICT,
+            Self::ForeignKey { .. } | Self::NotNull { .. } | Self::Check { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::InvalidValue { .. } => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// The column that caused the violation, if it is known.
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            Self::Unique { column, .. } | Self::ForeignKey { column, .. } => column.as_deref(),
+            Self::NotNull { column } => Some(column),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn problem(&self) -> Problem {
+        let problem = Problem::new(self.status_code()).with_detail(self);
+        match self.column() {
+            Some(column) => {
+                let message = match self {
+                    Self::Unique { .. } => "already exists",
+                    Self::ForeignKey { .. } => "does not exist",
+                    _ => "may not be null",
+                };
+                problem.with_error(column, vec![message])
+            }
+            None => problem,
+        }
+    }
+}
+
+impl Display for ConstraintViolation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Unique {
+                column: Some(column),
+                ..
+            } => write!(f, "A row with this {column} already exists"),
+            Self::Unique { constraint, .. } => {
+                write!(
+                    f,
+                    "A row violating unique constraint {constraint} already exists"
+                )
+            }
+            Self::ForeignKey {
+                column: Some(column),
+                ..
+            } => write!(f, "The row referred to by {column} does not exist"),
+            Self::ForeignKey { constraint, .. } => {
+                write!(f, "The row referred to by {constraint} does not exist")
+            }
+            Self::Referenced { constraint } => {
+                write!(f, "The row is still referred to by {constraint}")
+            }
+            Self::NotNull { column } => write!(f, "The {column} may not be null"),
+            Self::Check { constraint } => write!(f, "The row violates {constraint}"),
+            Self::InvalidValue => write!(f, "A value cannot be stored in its column"),
+        }
+    }
+}
+
+/// The SQLSTATE of the error that Postgres reports with `message`, for the errors that
+/// are violations.
+fn sqlstate(message: &str) -> Option<&'static str> {
+    const MESSAGES: [(&str, &str); 7] = [
+        ("duplicate key value violates unique constraint", "23505"),
+        ("violates foreign key constraint", "23503"),
+        ("violates not-null constraint", "23502"),
+        ("violates check constraint", "23514"),
+        ("invalid input syntax for type", "22P02"),
+        ("invalid input value for enum", "22P02"),
+        ("value too long for type", "22001"),
+    ];
+    MESSAGES
+        .iter()
+        .find(|(text, _)| message.contains(text))
+        .map(|(_, code)| *code)
+}
+
+/// Whether `error` was reported by the database and mentions a constraint or violation,
+/// such as a violation worded differently or in another language.
+fn may_be_violation(error: &DbErr) -> bool {
+    match error {
+        DbErr::Exec(message) | DbErr::Query(message) => {
+            let message = message.to_lowercase();
+            message.contains("error returned from database")
+                && (message.contains("constraint") || message.contains("violat"))
+        }
+        _ => false,
+    }
+}
+
+/// The double quoted names in `message`, in order.
+fn quoted(message: &str) -> Vec<&str> {
+    let parts = message.split('"').collect::<Vec<_>>();
+    // The last part follows the last quote, so it is never a name.
+    parts[..parts.len() - 1]
+        .chunks_exact(2)
+        .map(|pair| pair[1])
+        .collect()
+}
+
+/// Converts an error from writing to the table of `E`, naming the offending column of any
+/// constraint it violated.
+pub(crate) fn classify<E: EntityTrait>(error: DbErr) -> crate::Error {
+    let columns = E::Column::iter().collect::<Vec<_>>();
+    let columns = columns.iter().map(IdenStatic::as_str).collect::<Vec<_>>();
+    let table = E::default().table_name().to_owned();
+    match ConstraintViolation::parse(&error.to_string(), Some(&table), &columns) {
+        Some(violation) => violation.into(),
+        None => error.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{may_be_violation, ConstraintViolation};
+    use sea_orm::DbErr;
+
+    const COLUMNS: &[&str] = &["id", "slug", "author", "title"];
+
+    /// Parses `message` as sea-orm reports it for a write to `posts`.
+    fn parse(message: &str) -> Option<ConstraintViolation> {
+        let message = format!("Execution Error: error returned from database: {message}");
+        ConstraintViolation::parse(&message, Some("posts"), COLUMNS)
+    }
+
+    #[test]
+    fn unique() {
+        assert_eq!(
+            parse(r#"duplicate key value violates unique constraint "posts_slug_key""#),
+            Some(ConstraintViolation::Unique {
+                constraint: "posts_slug_key".to_owned(),
+                column: Some("slug".to_owned()),
+            })
+        );
+    }
+
+    #[test]
+    fn unique_with_custom_name() {
+        assert_eq!(
+            parse(r#"duplicate key value violates unique constraint "one_title_per_author""#),
+            Some(ConstraintViolation::Unique {
+                constraint: "one_title_per_author".to_owned(),
+                column: None,
+            })
+        );
+    }
+
+    #[test]
+    fn foreign_key() {
+        assert_eq!(
+            parse(
+                r#"insert or update on table "posts" violates foreign key constraint "posts_author_fkey""#
+            ),
+            Some(ConstraintViolation::ForeignKey {
+                constraint: "posts_author_fkey".to_owned(),
+                column: Some("author".to_owned()),
+            })
+        );
+    }
+
+    #[test]
+    fn referenced() {
+        assert_eq!(
+            parse(
+                r#"update or delete on table "posts" violates foreign key constraint "comments_post_fkey" on table "comments""#
+            ),
+            Some(ConstraintViolation::Referenced {
+                constraint: "comments_post_fkey".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn not_null() {
+        assert_eq!(
+            parse(
+                r#"null value in column "title" of relation "posts" violates not-null constraint"#
+            ),
+            Some(ConstraintViolation::NotNull {
+                column: "title".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(
+            parse(r#"new row for relation "posts" violates check constraint "posts_title_check""#),
+            Some(ConstraintViolation::Check {
+                constraint: "posts_title_check".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_value_leaves_out_the_value() {
+        let violation = parse(r#"invalid input syntax for type uuid: "secret""#).unwrap();
+        assert_eq!(violation, ConstraintViolation::InvalidValue);
+        assert!(!violation.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn other_errors() {
+        assert_eq!(parse(r#"relation "posts" does not exist"#), None);
+        assert_eq!(parse(r#"unterminated "quote"#), None);
+    }
+
+    #[test]
+    fn unrecognized_violations() {
+        let error =
+            |message: &str| DbErr::Query(format!("error returned from database: {message}"));
+        assert!(may_be_violation(&error(
+            r#"conflicting key value violates exclusion constraint "posts_during""#
+        )));
+        assert!(may_be_violation(&error(
+            "doppelter Schlüsselwert verletzt Unique-Constraint »posts_slug_key«"
+        )));
+        assert!(!may_be_violation(&error(
+            r#"relation "posts" does not exist"#
+        )));
+        assert!(!may_be_violation(&DbErr::Conn(
+            "constraint of the pool was violated".to_owned()
+        )));
+    }
+}
@@ -0,0 +1,10 @@
+use actix_web::HttpRequest;
+use sea_orm::DatabaseConnection;
+
+/// The request that a [`RestModel`](crate::RestModel) action is handling, as seen by the
+/// hooks of a resource.
+#[derive(Clone, Copy)]
+pub struct Context<'a> {
+    pub request: &'a HttpRequest,
+    pub db: &'a DatabaseConnection,
+}
@@ -0,0 +1,114 @@
+use crate::error::InvalidCursor;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+/// The secret used to sign the cursors handed out by [`CursorPagination`], so that
+/// clients cannot forge them to page through arbitrary rows.
+///
+/// Register it as app data for any service that uses cursor pagination:
+///
+/// ```ignore
+/// App::new().app_data(web::Data::new(CursorKey::new(secret.as_bytes())))
+/// ```
+///
+/// [`CursorPagination`]: crate::CursorPagination
+pub struct CursorKey(hmac::Key);
+
+impl CursorKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self(hmac::Key::new(hmac::HMAC_SHA256, secret))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Direction {
+    #[serde(rename = "n")]
+    Next,
+    #[serde(rename = "p")]
+    Previous,
+}
+
+/// The decoded contents of a cursor: the values of the ordering columns of the row on
+/// the edge of the page, and which way to page from there.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Cursor {
+    #[serde(rename = "d")]
+    pub direction: Direction,
+    /// The ordering the cursor was issued for, so it cannot be reused with another.
+    #[serde(rename = "o")]
+    pub ordering: String,
+    #[serde(rename = "v")]
+    pub values: Vec<Option<String>>,
+}
+
+impl Cursor {
+    /// Encodes the cursor as `base64(payload).base64(hmac(payload))`.
+    pub fn encode(&self, key: &CursorKey) -> String {
+        let payload = serde_json::to_vec(self).expect("cursor should serialize");
+        let tag = hmac::sign(&key.0, &payload);
+        format!(
+            "{}.{}",
+            base64::encode_config(&payload, base64::URL_SAFE_NO_PAD),
+            base64::encode_config(tag.as_ref(), base64::URL_SAFE_NO_PAD),
+        )
+    }
+
+    pub fn decode(cursor: &str, key: &CursorKey) -> crate::Result<Self> {
+        let (payload, tag) = cursor.split_once('.').ok_or_else(InvalidCursor)?;
+        let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| InvalidCursor())?;
+        let tag =
+            base64::decode_config(tag, base64::URL_SAFE_NO_PAD).map_err(|_| InvalidCursor())?;
+        hmac::verify(&key.0, &payload, &tag).map_err(|_| InvalidCursor())?;
+        serde_json::from_slice(&payload).map_err(|_| InvalidCursor())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cursor, CursorKey, Direction};
+
+    fn cursor() -> Cursor {
+        Cursor {
+            direction: Direction::Previous,
+            ordering: "-title,id".to_owned(),
+            values: vec![Some("Hello, world".to_owned()), None],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = CursorKey::new(b"secret");
+        let encoded = cursor().encode(&key);
+        assert_eq!(Cursor::decode(&encoded, &key).unwrap(), cursor());
+    }
+
+    #[test]
+    fn tampered_payload() {
+        let key = CursorKey::new(b"secret");
+        let encoded = cursor().encode(&key);
+        let (_, tag) = encoded.split_once('.').unwrap();
+        // A payload of our own, paired with the signature of another cursor.
+        let forged = Cursor {
+            values: vec![Some("Goodbye".to_owned()), None],
+            ..cursor()
+        }
+        .encode(&key);
+        let (payload, _) = forged.split_once('.').unwrap();
+        assert!(Cursor::decode(&format!("{payload}.{tag}"), &key).is_err());
+    }
+
+    #[test]
+    fn signed_with_another_key() {
+        let encoded = cursor().encode(&CursorKey::new(b"another secret"));
+        assert!(Cursor::decode(&encoded, &CursorKey::new(b"secret")).is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let key = CursorKey::new(b"secret");
+        for encoded in ["", "no-tag", "not base64!.tag", "e30.e30"] {
+            assert!(Cursor::decode(encoded, &key).is_err(), "{encoded}");
+        }
+    }
+}
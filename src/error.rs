@@ -1,6 +1,10 @@
-use actix_web::http::StatusCode;
+use crate::constraint::ConstraintViolation;
+use actix_web::http::{header, StatusCode};
 use actix_web::{HttpResponse, ResponseError};
-use std::fmt::{self, Display, Formatter};
+use serde::Serialize;
+use serde_json::Value as Json;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display, Formatter};
 
 #[derive(Debug)]
 pub struct Error(ErrorInternals);
@@ -8,6 +12,8 @@ pub struct Error(ErrorInternals);
 #[derive(Debug)]
 enum ErrorInternals {
     HttpError(actix_web::Error),
+    Problem(Problem),
+    Constraint(ConstraintViolation),
     Custom {
         status_code: StatusCode,
         message: String,
@@ -22,6 +28,8 @@ impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match &self.0 {
             ErrorInternals::HttpError(source) => write!(f, "woof::Error: {}", source),
+            ErrorInternals::Problem(problem) => write!(f, "woof::Error: {}", problem.title),
+            ErrorInternals::Constraint(violation) => write!(f, "woof::Error: {}", violation),
             ErrorInternals::Other { source, .. } => {
                 write!(f, "woof::Error: {}", source)
             }
@@ -37,24 +45,154 @@ impl std::error::Error for Error {
         match &self.0 {
             ErrorInternals::HttpError(source) => Some(source),
             ErrorInternals::Other { source, .. } => Some(source.as_ref()),
-            ErrorInternals::Custom { .. } => None,
+            ErrorInternals::Problem(..)
+            | ErrorInternals::Constraint(..)
+            | ErrorInternals::Custom { .. } => None,
         }
     }
 }
 
-impl ResponseError for Error {
-    fn error_response(&self) -> HttpResponse {
+impl Error {
+    /// The database constraint that caused this error, if any.
+    pub fn constraint_violation(&self) -> Option<&ConstraintViolation> {
+        match &self.0 {
+            ErrorInternals::Constraint(violation) => Some(violation),
+            _ => None,
+        }
+    }
+
+    /// Describes this error as an RFC 7807 problem, which is what is sent to the client.
+    ///
+    /// The details of server errors are left out, as they may leak the internals of the
+    /// application, such as the text of database errors.
+    pub fn problem(&self) -> Problem {
         match &self.0 {
-            ErrorInternals::HttpError(error) => error.error_response(),
+            ErrorInternals::HttpError(error) => Problem::from(error),
+            ErrorInternals::Problem(problem) => problem.clone(),
+            ErrorInternals::Constraint(violation) => violation.problem(),
             ErrorInternals::Other {
                 status_code,
                 source,
-            } => HttpResponse::build(*status_code).body(source.to_string()),
+            } => Problem::new(*status_code).with_client_detail(source),
             ErrorInternals::Custom {
                 status_code,
                 message,
-            } => HttpResponse::build(*status_code).body(message.clone()),
+            } => Problem::new(*status_code).with_client_detail(message),
+        }
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match &self.0 {
+            ErrorInternals::HttpError(error) => error.as_response_error().status_code(),
+            ErrorInternals::Problem(problem) => problem.status_code(),
+            ErrorInternals::Constraint(violation) => violation.status_code(),
+            ErrorInternals::Other { status_code, .. } => *status_code,
+            ErrorInternals::Custom { status_code, .. } => *status_code,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.status_code().is_server_error() {
+            log::error!("{}", self);
         }
+        self.problem().render()
+    }
+}
+
+/// The body of an error response, as described by RFC 7807.
+///
+/// Errors are rendered as `application/problem+json` by default. Register an
+/// [`ErrorRenderer`] as app data to send them in a different format.
+#[derive(Clone, Debug, Serialize)]
+pub struct Problem {
+    /// A URI identifying the type of problem, `about:blank` if it is described by the
+    /// status alone.
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Further information about the problem, such as the errors of each invalid field.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, Json>,
+}
+
+impl Problem {
+    pub fn new(status_code: StatusCode) -> Self {
+        Self {
+            type_uri: "about:blank".to_owned(),
+            title: status_code
+                .canonical_reason()
+                .unwrap_or("Unknown Error")
+                .to_owned(),
+            status: status_code.as_u16(),
+            detail: None,
+            errors: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Display) -> Self {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    pub fn with_error(mut self, key: impl Into<String>, error: impl Into<Json>) -> Self {
+        self.errors.insert(key.into(), error.into());
+        self
+    }
+
+    /// Sets the detail only if this is a client error.
+    fn with_client_detail(self, detail: impl Display) -> Self {
+        if self.status_code().is_client_error() {
+            self.with_detail(detail)
+        } else {
+            self
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Renders this problem as `application/problem+json`.
+    pub fn render(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .insert_header((header::CONTENT_TYPE, "application/problem+json"))
+            .body(serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+impl From<&actix_web::Error> for Problem {
+    fn from(error: &actix_web::Error) -> Self {
+        match error.as_error::<Error>() {
+            Some(error) => error.problem(),
+            None => Problem::new(error.as_response_error().status_code()).with_client_detail(error),
+        }
+    }
+}
+
+/// Renders the errors of every resource, in place of the default `application/problem+json`.
+/// Register one as `web::Data<ErrorRenderer>` in the app data.
+pub struct ErrorRenderer(Render);
+
+type Render = Box<dyn Fn(&Problem) -> HttpResponse + Send + Sync>;
+
+impl ErrorRenderer {
+    pub fn new(render: impl Fn(&Problem) -> HttpResponse + Send + Sync + 'static) -> Self {
+        Self(Box::new(render))
+    }
+
+    pub fn render(&self, problem: &Problem) -> HttpResponse {
+        (self.0)(problem)
+    }
+}
+
+impl From<Problem> for Error {
+    fn from(problem: Problem) -> Self {
+        Self(ErrorInternals::Problem(problem))
     }
 }
 
@@ -73,8 +211,29 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<ConstraintViolation> for Error {
+    fn from(violation: ConstraintViolation) -> Self {
+        Self(ErrorInternals::Constraint(violation))
+    }
+}
+
 impl From<sea_orm::error::DbErr> for Error {
     fn from(error: sea_orm::error::DbErr) -> Self {
+        if let sea_orm::error::DbErr::RecordNotFound(..) = error {
+            return NotFound();
+        }
+        if let Some(violation) = ConstraintViolation::from_error(&error) {
+            return violation.into();
+        }
+        Self(ErrorInternals::Other {
+            status_code: StatusCode::INTERNAL_SERVER_ERROR,
+            source: Box::new(error),
+        })
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
         Self(ErrorInternals::Other {
             status_code: StatusCode::INTERNAL_SERVER_ERROR,
             source: Box::new(error),
@@ -84,6 +243,14 @@ impl From<sea_orm::error::DbErr> for Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[allow(non_snake_case)]
+pub fn NotFound() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::NOT_FOUND,
+        message: "Not found".to_owned(),
+    })
+}
+
 #[allow(non_snake_case)]
 pub fn MissingPathSegment(segment: impl Display) -> Error {
     Error(ErrorInternals::Custom {
@@ -92,6 +259,22 @@ pub fn MissingPathSegment(segment: impl Display) -> Error {
     })
 }
 
+#[allow(non_snake_case)]
+pub fn UnsupportedPrimaryKey() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::INTERNAL_SERVER_ERROR,
+        message: "Primary keys of more than six columns are not supported".to_owned(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn UnsupportedReplace(backend: impl Debug) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::INTERNAL_SERVER_ERROR,
+        message: format!("Replacing rows is only supported on Postgres, not {backend:?}"),
+    })
+}
+
 #[allow(non_snake_case)]
 pub fn InvalidPathSegment(segment: impl Display) -> Error {
     Error(ErrorInternals::Custom {
@@ -99,3 +282,139 @@ pub fn InvalidPathSegment(segment: impl Display) -> Error {
         message: format!("Invalid path segment {segment}"),
     })
 }
+
+#[allow(non_snake_case)]
+pub fn UnknownLookup(lookup: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Unknown lookup {lookup}"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn InvalidLookupValue(lookup: impl Display, value: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Invalid value {value} for lookup {lookup}"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn InvalidParameterValue(parameter: impl Display, value: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Invalid value {value} for {parameter}"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn PageOutOfRange() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: "The requested page is out of range".to_owned(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn UnknownField(field: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Unknown field {field}"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn UnknownOrdering(field: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Cannot order by {field}"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn InvalidCursor() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: "Invalid cursor".to_owned(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn MissingCursorKey() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::INTERNAL_SERVER_ERROR,
+        message: "Cursor pagination requires a woof::CursorKey in the app data".to_owned(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn UnknownExpansion(name: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Cannot expand {name}"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn ExpansionTooDeep(depth: usize) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Expansions may not be nested more than {depth} deep"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn TooManyExpandedRows(limit: u64) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::BAD_REQUEST,
+        message: format!("Expansions may not find more than {limit} rows"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn UnsupportedExpansion(name: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::INTERNAL_SERVER_ERROR,
+        message: format!("Cannot expand {name} through a junction table"),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn UnsupportedMediaType() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        message: "Expected a JSON request body".to_owned(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn Unauthorized(message: impl Display) -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::UNAUTHORIZED,
+        message: message.to_string(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn MissingDatabase() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::INTERNAL_SERVER_ERROR,
+        message: "Authentication requires a DatabaseConnection in the app data".to_owned(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn Forbidden() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::FORBIDDEN,
+        message: "You do not have permission to perform this action".to_owned(),
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn MethodNotAllowed() -> Error {
+    Error(ErrorInternals::Custom {
+        status_code: StatusCode::METHOD_NOT_ALLOWED,
+        message: "This method is not allowed on this resource".to_owned(),
+    })
+}
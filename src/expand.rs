@@ -0,0 +1,274 @@
+use crate::error::{ExpansionTooDeep, TooManyExpandedRows, UnknownExpansion, UnsupportedExpansion};
+use crate::{permission, value, Action, Context, Hooks, Rest};
+use async_trait::async_trait;
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, IdenStatic, Identity, IntoActiveModel, ModelTrait,
+    QueryFilter, QuerySelect, Related, RelationType, Value,
+};
+use serde_json::Value as Json;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// A related resource that clients may inline into responses with `?expand=`, declared
+/// by [`Rest::expansions`].
+///
+/// Expanding a relation to a single row, such as the author of a post, replaces the field
+/// of that name with the related resource. Expanding a relation to many rows, such as the
+/// comments on a post, sets the field to a list of them. Either way, the related rows of
+/// every item in a response are loaded with one query, which may find no more than
+/// [`Rest::max_expanded_rows`] of them.
+///
+/// The columns of the relation must be serialized by [`Rest::Repr`] under their own
+/// names, as they are by the entity's `Model`. Related rows are found through the
+/// [`Hooks::base_query`] of their resource, so expansions cannot reveal hidden rows, and
+/// must be retrievable under its [`Rest::permission`]. Rows that the permission refuses
+/// are left out, as if they did not exist.
+pub struct Expansion<T> {
+    name: String,
+    expander: Box<dyn Expander>,
+    _pd: PhantomData<T>,
+}
+
+impl<T: Rest + 'static> Expansion<T> {
+    /// Expands `name` into the rows of the resource `R` that are related to each row.
+    ///
+    /// Relations through a junction table are not supported, and requests to expand them
+    /// fail.
+    pub fn new<R>(name: impl Into<String>) -> Self
+    where
+        R: Hooks + 'static,
+        T::Entity: Related<R::Entity>,
+        <R::Entity as EntityTrait>::Model: IntoActiveModel<R::ActiveModel>,
+    {
+        Self {
+            name: name.into(),
+            expander: Box::new(Relation::<T, R>(PhantomData)),
+            _pd: PhantomData,
+        }
+    }
+}
+
+/// The requested expansions, such as `author,comments.author`, as a tree.
+#[derive(Default)]
+struct Tree(BTreeMap<String, Tree>);
+
+impl Tree {
+    fn parse(requested: &str) -> Self {
+        let mut tree = Self::default();
+        for path in requested
+            .split(',')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+        {
+            path.split('.').fold(&mut tree, |tree, name| {
+                tree.0.entry(name.to_owned()).or_default()
+            });
+        }
+        tree
+    }
+
+    fn depth(&self) -> usize {
+        self.0
+            .values()
+            .map(|tree| tree.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[async_trait(?Send)]
+trait Expander {
+    /// Whether the relation can be expanded, which it cannot through a junction table.
+    fn supported(&self) -> bool;
+
+    /// The columns of the expanded resource that the related rows are found by.
+    fn keys(&self) -> Vec<String>;
+
+    /// Loads the expanded value of each of `items`, in the same order.
+    async fn expand(
+        &self,
+        items: &[Json],
+        tree: &Tree,
+        ctx: &Context<'_>,
+    ) -> crate::Result<Vec<Json>>;
+}
+
+struct Relation<T, R>(PhantomData<(T, R)>);
+
+#[async_trait(?Send)]
+impl<T, R> Expander for Relation<T, R>
+where
+    T: Rest + 'static,
+    R: Hooks + 'static,
+    T::Entity: Related<R::Entity>,
+    <R::Entity as EntityTrait>::Model: IntoActiveModel<R::ActiveModel>,
+{
+    fn supported(&self) -> bool {
+        <T::Entity as Related<R::Entity>>::via().is_none()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        columns::<T::Entity>(<T::Entity as Related<R::Entity>>::to().from_col)
+            .iter()
+            .map(|column| column.as_str().to_owned())
+            .collect()
+    }
+
+    async fn expand(
+        &self,
+        items: &[Json],
+        tree: &Tree,
+        ctx: &Context<'_>,
+    ) -> crate::Result<Vec<Json>> {
+        let relation = <T::Entity as Related<R::Entity>>::to();
+        let many = matches!(relation.rel_type, RelationType::HasMany);
+        let from = columns::<T::Entity>(relation.from_col);
+        let to = columns::<R::Entity>(relation.to_col);
+
+        let keys = items
+            .iter()
+            .map(|item| from.iter().map(|column| read(item, column)).collect())
+            .collect::<Vec<Option<Vec<Value>>>>();
+        let condition = keys
+            .iter()
+            .flatten()
+            .fold(Condition::any(), |condition, key| {
+                condition.add(
+                    to.iter()
+                        .zip(key)
+                        .fold(Condition::all(), |condition, (column, value)| {
+                            condition.add(column.eq(value.clone()))
+                        }),
+                )
+            });
+
+        let mut related_keys = vec![];
+        let mut related = vec![];
+        if keys.iter().any(Option::is_some) {
+            permission::authorize::<R>(ctx, Action::Retrieve).await?;
+            let limit = T::max_expanded_rows();
+            let models = R::base_query(ctx)
+                .filter(condition)
+                .limit(limit + 1)
+                .all(ctx.db)
+                .await?;
+            if models.len() as u64 > limit {
+                return Err(TooManyExpandedRows(limit));
+            }
+            for model in models {
+                if !permission::allows_object::<R>(ctx, Action::Retrieve, &model).await? {
+                    continue;
+                }
+                related_keys.push(to.iter().map(|column| model.get(*column)).collect());
+                related.push(serde_json::to_value(R::Repr::from(model))?);
+            }
+        }
+        expand_tree::<R>(&mut related, tree, ctx).await?;
+
+        Ok(keys
+            .iter()
+            .map(|key| {
+                let mut matches = related_keys
+                    .iter()
+                    .zip(&related)
+                    .filter(|(related_key, _)| Some(*related_key) == key.as_ref())
+                    .map(|(_, related)| related.clone());
+                if many {
+                    Json::Array(matches.collect())
+                } else {
+                    matches.next().unwrap_or(Json::Null)
+                }
+            })
+            .collect::<Vec<Json>>())
+    }
+}
+
+fn columns<E: EntityTrait>(identity: Identity) -> Vec<E::Column> {
+    match identity {
+        Identity::Unary(a) => vec![a],
+        Identity::Binary(a, b) => vec![a, b],
+        Identity::Ternary(a, b, c) => vec![a, b, c],
+    }
+    .into_iter()
+    .filter_map(|iden| E::Column::from_str(&iden.to_string()).ok())
+    .collect()
+}
+
+/// Reads the value of `column` from a serialized item, if it is present and not null.
+fn read<C: ColumnTrait>(item: &Json, column: &C) -> Option<Value> {
+    let raw = match item.get(column.as_str())? {
+        Json::Null => return None,
+        Json::String(raw) => raw.clone(),
+        json => json.to_string(),
+    };
+    value::parse(column.def().get_column_type(), &raw)
+}
+
+/// The columns of `T` that the requested expansions are found by, which must be loaded
+/// even if the response leaves them out.
+pub(crate) fn keys<T: Rest + 'static>(requested: Option<&str>) -> Vec<String> {
+    let tree = Tree::parse(requested.unwrap_or_default());
+    T::expansions()
+        .iter()
+        .filter(|expansion| tree.0.contains_key(&expansion.name))
+        .flat_map(|expansion| expansion.expander.keys())
+        .collect()
+}
+
+/// Inlines the related resources named by the `?expand=` parameter into each of the
+/// serialized `items` of the resource `T`.
+pub(crate) async fn expand<T: Rest + 'static>(
+    items: &mut [Json],
+    requested: Option<&str>,
+    ctx: &Context<'_>,
+) -> crate::Result<()> {
+    let tree = match requested {
+        Some(requested) => Tree::parse(requested),
+        None => return Ok(()),
+    };
+    if tree.depth() > T::max_expansion_depth() {
+        return Err(ExpansionTooDeep(T::max_expansion_depth()));
+    }
+    expand_tree::<T>(items, &tree, ctx).await
+}
+
+async fn expand_tree<T: Rest + 'static>(
+    items: &mut [Json],
+    tree: &Tree,
+    ctx: &Context<'_>,
+) -> crate::Result<()> {
+    if tree.0.is_empty() {
+        return Ok(());
+    }
+    let expansions = T::expansions();
+    let requested = tree
+        .0
+        .iter()
+        .map(|(name, tree)| {
+            expansions
+                .iter()
+                .find(|expansion| &expansion.name == name)
+                .ok_or_else(|| UnknownExpansion(name))
+                .and_then(|expansion| match expansion.expander.supported() {
+                    true => Ok((name, expansion, tree)),
+                    false => Err(UnsupportedExpansion(name)),
+                })
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    if items.is_empty() {
+        return Ok(());
+    }
+    let mut expanded = vec![];
+    for (name, expansion, tree) in requested {
+        expanded.push((name, expansion.expander.expand(items, tree, ctx).await?));
+    }
+    for (name, values) in expanded {
+        for (item, value) in items.iter_mut().zip(values) {
+            if let Json::Object(item) = item {
+                item.insert(name.clone(), value);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,8 @@
+/// The user that authenticated a request.
+pub(crate) struct Authenticated<U>(pub U);
+
+/// Marks a request made by an authenticated user, whatever their type, along with the
+/// id they own rows by, if the backend knows it.
+pub(crate) struct Identity {
+    pub id: Option<String>,
+}
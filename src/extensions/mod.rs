@@ -1,3 +1,5 @@
+mod authenticated;
 mod primary_key_extension;
 
+pub(crate) use authenticated::{Authenticated, Identity};
 pub(crate) use primary_key_extension::PrimaryKeyExtension;
@@ -0,0 +1,39 @@
+use crate::payload;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
+
+/// A JSON request body, like [`web::Json`], whose errors name the field that could not be
+/// deserialized.
+///
+/// The body is read as [`web::Json`] reads it, under the limit and content type of the
+/// [`web::JsonConfig`], but only checked to be JSON. `T` is then deserialized once from
+/// the text of the body, which the errors are located in.
+pub(crate) struct Body<T>(pub T);
+
+impl<T: DeserializeOwned + 'static> FromRequest for Body<T> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<Box<RawValue>>::from_request(req, payload);
+        Box::pin(async move {
+            let json = json.await.map_err(payload::json_error)?;
+            let text = json.get();
+            serde_json::from_str(text)
+                .map(Body)
+                .map_err(|error| payload::invalid_json(&error, Some(text.as_bytes())).into())
+        })
+    }
+}
+
+impl<T> Deref for Body<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
@@ -0,0 +1,46 @@
+use crate::error::Unauthorized;
+use crate::extensions::Authenticated;
+use crate::Context;
+use actix_web::{dev::Payload, Error, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+use std::ops::Deref;
+
+/// The user that made a request, as identified by the
+/// [`Authentication`](crate::Authentication) middleware.
+///
+/// Anonymous requests are rejected with 401. Extract an `Option<CurrentUser<U>>` to
+/// allow them.
+#[derive(Clone, Debug)]
+pub struct CurrentUser<U>(pub U);
+
+impl<U: Clone + 'static> CurrentUser<U> {
+    /// The user that made the request being handled by a hook.
+    pub fn from_context(ctx: &Context<'_>) -> crate::Result<Self> {
+        Self::get(ctx.request)
+    }
+
+    fn get(request: &HttpRequest) -> crate::Result<Self> {
+        request
+            .extensions()
+            .get::<Authenticated<U>>()
+            .map(|authenticated| Self(authenticated.0.clone()))
+            .ok_or_else(|| Unauthorized("Authentication required"))
+    }
+}
+
+impl<U: Clone + 'static> FromRequest for CurrentUser<U> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Self::get(req).map_err(Into::into))
+    }
+}
+
+impl<U> Deref for CurrentUser<U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        &self.0
+    }
+}
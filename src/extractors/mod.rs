@@ -1,3 +1,7 @@
+mod body;
+mod current_user;
 mod primary_key;
 
+pub(crate) use body::Body;
+pub use current_user::CurrentUser;
 pub use primary_key::PrimaryKey;
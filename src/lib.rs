@@ -1,14 +1,47 @@
+pub mod auth;
 pub mod error;
 mod pagination;
 
+mod action;
+mod constraint;
+mod context;
+mod cursor;
+mod expand;
 mod extensions;
 mod extractors;
+mod lookup;
 mod middleware;
+mod nested;
+mod ordering;
+mod patch;
+mod payload;
+mod permission;
 mod rest_model;
+mod shape;
 mod traits;
+mod upsert;
+mod value;
 
-pub use error::{Error, Result};
-pub use extractors::PrimaryKey;
-pub use pagination::{CursorPagination, LimitOffsetPagination, PageNumberPagination};
+pub use action::{Action, Actions};
+pub use constraint::ConstraintViolation;
+pub use context::Context;
+pub use cursor::CursorKey;
+pub use error::{Error, ErrorRenderer, Problem, Result};
+pub use expand::Expansion;
+pub use extractors::{CurrentUser, PrimaryKey};
+pub use lookup::{Lookup, LookupFilter};
+pub use middleware::Authentication;
+pub use pagination::{
+    CountPolicy, CursorPagination, LimitOffsetPagination, PageNumberPagination, Paginate,
+};
+pub use patch::Patch;
+pub use permission::{AllowAny, And, IsAuthenticated, IsOwner, Not, Or, Permission, ReadOnly};
 pub use rest_model::RestModel;
-pub use traits::{Create, Filter, Rest, Update};
+pub use traits::{id_from_path, Create, Filter, Hooks, Rest, Update, Validate, ValidationErrors};
+pub use woof_derive::{Create, Filter, Rest, Update};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use actix_web;
+    pub use sea_orm;
+}
@@ -0,0 +1,269 @@
+use crate::error::{InvalidLookupValue, InvalidParameterValue, UnknownField, UnknownLookup};
+use crate::{value, Filter};
+use sea_orm::{
+    sea_query::{Alias, Expr, Func, SimpleExpr},
+    ColumnTrait, ColumnType, Condition, EntityTrait, IdenStatic, Iterable, Value,
+};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A Django-style field lookup, describing how a column is compared to a filter value.
+///
+/// The case-insensitive and pattern lookups only make sense for text; when given any
+/// other kind of value they fall back to an exact comparison.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lookup {
+    Exact(Value),
+    IExact(Value),
+    Contains(Value),
+    IContains(Value),
+    StartsWith(Value),
+    IStartsWith(Value),
+    EndsWith(Value),
+    IEndsWith(Value),
+    Gt(Value),
+    Gte(Value),
+    Lt(Value),
+    Lte(Value),
+    In(Vec<Value>),
+    IsNull(bool),
+    Range(Value, Value),
+}
+
+impl Lookup {
+    /// The names by which each lookup is referred to in query strings, e.g. `title__icontains`.
+    pub const NAMES: [&'static str; 15] = [
+        "exact",
+        "iexact",
+        "contains",
+        "icontains",
+        "startswith",
+        "istartswith",
+        "endswith",
+        "iendswith",
+        "gt",
+        "gte",
+        "lt",
+        "lte",
+        "in",
+        "isnull",
+        "range",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Exact(..) => "exact",
+            Self::IExact(..) => "iexact",
+            Self::Contains(..) => "contains",
+            Self::IContains(..) => "icontains",
+            Self::StartsWith(..) => "startswith",
+            Self::IStartsWith(..) => "istartswith",
+            Self::EndsWith(..) => "endswith",
+            Self::IEndsWith(..) => "iendswith",
+            Self::Gt(..) => "gt",
+            Self::Gte(..) => "gte",
+            Self::Lt(..) => "lt",
+            Self::Lte(..) => "lte",
+            Self::In(..) => "in",
+            Self::IsNull(..) => "isnull",
+            Self::Range(..) => "range",
+        }
+    }
+
+    /// Parses the raw query string value of the lookup called `name` on a column of the
+    /// given type. `in` takes a comma separated list, and `range` exactly two values.
+    pub fn parse(name: &str, column_type: &ColumnType, raw: &str) -> crate::Result<Self> {
+        let one = |raw: &str| {
+            value::parse(column_type, raw).ok_or_else(|| InvalidLookupValue(name, raw))
+        };
+        let lookup = match name {
+            "exact" => Self::Exact(one(raw)?),
+            "iexact" => Self::IExact(one(raw)?),
+            "contains" => Self::Contains(one(raw)?),
+            "icontains" => Self::IContains(one(raw)?),
+            "startswith" => Self::StartsWith(one(raw)?),
+            "istartswith" => Self::IStartsWith(one(raw)?),
+            "endswith" => Self::EndsWith(one(raw)?),
+            "iendswith" => Self::IEndsWith(one(raw)?),
+            "gt" => Self::Gt(one(raw)?),
+            "gte" => Self::Gte(one(raw)?),
+            "lt" => Self::Lt(one(raw)?),
+            "lte" => Self::Lte(one(raw)?),
+            "in" => Self::In(raw.split(',').map(one).collect::<crate::Result<_>>()?),
+            "isnull" => Self::IsNull(
+                raw.parse()
+                    .map_err(|_| InvalidLookupValue(name, raw))?,
+            ),
+            "range" => {
+                let (start, end) = raw
+                    .split_once(',')
+                    .ok_or_else(|| InvalidLookupValue(name, raw))?;
+                Self::Range(one(start)?, one(end)?)
+            }
+            _ => return Err(UnknownLookup(name)),
+        };
+        Ok(lookup)
+    }
+
+    pub fn condition<C: ColumnTrait>(self, column: C) -> SimpleExpr {
+        match self {
+            Self::Exact(value) => column.eq(value),
+            Self::Gt(value) => column.gt(value),
+            Self::Gte(value) => column.gte(value),
+            Self::Lt(value) => column.lt(value),
+            Self::Lte(value) => column.lte(value),
+            Self::In(values) => column.is_in(values),
+            Self::IsNull(true) => column.is_null(),
+            Self::IsNull(false) => column.is_not_null(),
+            Self::Range(start, end) => column.between(start, end),
+            Self::IExact(Value::String(Some(text))) => lower(column).eq(text.to_lowercase()),
+            Self::Contains(Value::String(Some(text))) => {
+                column.like(&format!("%{}%", escape_like(&text)))
+            }
+            Self::IContains(Value::String(Some(text))) => {
+                lower(column).like(&format!("%{}%", escape_like(&text).to_lowercase()))
+            }
+            Self::StartsWith(Value::String(Some(text))) => {
+                column.like(&format!("{}%", escape_like(&text)))
+            }
+            Self::IStartsWith(Value::String(Some(text))) => {
+                lower(column).like(&format!("{}%", escape_like(&text).to_lowercase()))
+            }
+            Self::EndsWith(Value::String(Some(text))) => {
+                column.like(&format!("%{}", escape_like(&text)))
+            }
+            Self::IEndsWith(Value::String(Some(text))) => {
+                lower(column).like(&format!("%{}", escape_like(&text).to_lowercase()))
+            }
+            Self::IExact(value)
+            | Self::Contains(value)
+            | Self::IContains(value)
+            | Self::StartsWith(value)
+            | Self::IStartsWith(value)
+            | Self::EndsWith(value)
+            | Self::IEndsWith(value) => column.eq(value),
+        }
+    }
+}
+
+fn lower<C: ColumnTrait>(column: C) -> Expr {
+    Expr::expr(Func::cust(Alias::new("LOWER")).arg(Expr::tbl(column.entity_name(), column)))
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// A [`Filter`] for any entity which reads its lookups straight from the query string,
+/// Django style: `?created_at__gte=2022-01-01T00:00:00Z&username__in=a,b&title__icontains=dog`.
+///
+/// Every column of the entity can be filtered on; a key without a lookup suffix is an
+/// `exact` lookup. Unknown columns, lookups and unparseable values are rejected when
+/// the query string is deserialized.
+pub struct LookupFilter<E: EntityTrait> {
+    limit: Option<usize>,
+    offset: Option<usize>,
+    page: Option<usize>,
+    cursor: Option<String>,
+    ordering: Option<String>,
+    lookups: Vec<(E::Column, Lookup)>,
+}
+
+impl<E: EntityTrait> LookupFilter<E> {
+    /// Reads one parameter of the query string, which is rejected as soon as it is read so
+    /// that errors can name it.
+    fn read(&mut self, key: String, raw: String) -> crate::Result<()> {
+        let number = || raw.parse().map_err(|_| InvalidParameterValue(&key, &raw));
+        match key.as_str() {
+            "limit" => self.limit = Some(number()?),
+            "offset" => self.offset = Some(number()?),
+            "page" => self.page = Some(number()?),
+            "cursor" => self.cursor = Some(raw),
+            "ordering" => self.ordering = Some(raw),
+            // Read by the handlers, as they shape the response rather than filtering it.
+            "expand" | "fields" | "omit" => {}
+            _ => {
+                let (field, name) = match key.rsplit_once("__") {
+                    Some((field, name)) if Lookup::NAMES.contains(&name) => (field, name),
+                    _ => (key.as_str(), "exact"),
+                };
+                let column = E::Column::iter()
+                    .find(|column| column.as_str() == field)
+                    .ok_or_else(|| UnknownField(field))?;
+                let lookup = Lookup::parse(name, column.def().get_column_type(), &raw)?;
+                self.lookups.push((column, lookup));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de, E: EntityTrait> Deserialize<'de> for LookupFilter<E> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Query<E>(PhantomData<E>);
+
+        impl<'de, E: EntityTrait> Visitor<'de> for Query<E> {
+            type Value = LookupFilter<E>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a query string")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut filter = LookupFilter {
+                    limit: None,
+                    offset: None,
+                    page: None,
+                    cursor: None,
+                    ordering: None,
+                    lookups: vec![],
+                };
+                while let Some((key, raw)) = map.next_entry()? {
+                    filter.read(key, raw).map_err(|error| {
+                        let problem = error.problem();
+                        de::Error::custom(problem.detail.unwrap_or(problem.title))
+                    })?;
+                }
+                Ok(filter)
+            }
+        }
+
+        deserializer.deserialize_map(Query(PhantomData))
+    }
+}
+
+impl<E: EntityTrait> Filter for LookupFilter<E> {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(20)
+    }
+
+    fn offset(&self) -> usize {
+        self.offset.unwrap_or(0)
+    }
+
+    fn page(&self) -> usize {
+        self.page.unwrap_or(0)
+    }
+
+    fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    fn ordering(&self) -> Option<&str> {
+        self.ordering.as_deref()
+    }
+
+    fn condition(&self) -> Condition {
+        self.lookups
+            .iter()
+            .fold(Condition::all(), |condition, (column, lookup)| {
+                condition.add(lookup.clone().condition(*column))
+            })
+    }
+}
@@ -0,0 +1,99 @@
+use super::render_errors::render;
+use crate::auth::Authenticate;
+use crate::extensions::{Authenticated, Identity};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, StatusCode},
+    Error, HttpMessage,
+};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Identifies the user making each request with the backend `A`, so that handlers and
+/// hooks can read them as a [`CurrentUser`](crate::CurrentUser).
+///
+/// Anonymous requests pass through, while requests with invalid credentials are rejected
+/// with 401. Every 401 response is sent with the backend's `WWW-Authenticate` challenge.
+/// Backends that look users up need the `DatabaseConnection` registered as app data.
+pub struct Authentication<A>(Rc<A>);
+
+impl<A> Authentication<A> {
+    pub fn new(backend: A) -> Self {
+        Self(Rc::new(backend))
+    }
+}
+
+impl<S, B, A> Transform<S, ServiceRequest> for Authentication<A>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    A: Authenticate + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AuthenticationMiddleware<A, S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthenticationMiddleware {
+            backend: self.0.clone(),
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct AuthenticationMiddleware<A, S> {
+    backend: Rc<A>,
+    service: Rc<S>,
+}
+
+impl<S, B, A> Service<ServiceRequest> for AuthenticationMiddleware<A, S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    A: Authenticate + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let backend = self.backend.clone();
+        let service = self.service.clone();
+        Box::pin(async move {
+            let (request, payload) = req.into_parts();
+            let mut response = match backend.authenticate(&request).await {
+                Ok(user) => {
+                    if let Some(user) = user {
+                        let id = backend.user_id(&user);
+                        let mut extensions = request.extensions_mut();
+                        extensions.insert(Identity { id });
+                        extensions.insert(Authenticated(user));
+                    }
+                    let req = ServiceRequest::from_parts(request, payload);
+                    service.call(req).await?.map_into_boxed_body()
+                }
+                Err(error) => {
+                    let response = render(&request, &error.problem());
+                    ServiceResponse::new(request, response)
+                }
+            };
+
+            let unauthorized = response.status() == StatusCode::UNAUTHORIZED;
+            let headers = response.headers_mut();
+            if unauthorized && !headers.contains_key(header::WWW_AUTHENTICATE) {
+                if let Ok(challenge) = backend.challenge().parse() {
+                    headers.insert(header::WWW_AUTHENTICATE, challenge);
+                }
+            }
+            Ok(response)
+        })
+    }
+}
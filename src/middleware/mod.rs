@@ -1,3 +1,7 @@
+mod authentication;
 mod primary_key;
+mod render_errors;
 
+pub use authentication::Authentication;
 pub use primary_key::PrimaryKey;
+pub use render_errors::RenderErrors;
@@ -55,7 +55,7 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        if let Some(pk) = T::id_from_path(None, req.match_info()).ok() {
+        if let Ok(pk) = T::id_from_path(None, req.match_info()) {
             req.extensions_mut().insert(PrimaryKeyExtension::<T>(pk));
         }
 
@@ -0,0 +1,78 @@
+use crate::error::{ErrorRenderer, Problem};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    web, Error, HttpRequest, HttpResponse,
+};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+
+/// Renders every error response of the services it wraps as a [`Problem`], using the
+/// [`ErrorRenderer`] in the app data if there is one.
+///
+/// Errors raised by woof are already problems, but those raised by actix-web itself, such
+/// as when a request body cannot be read, are not.
+#[derive(Default)]
+pub struct RenderErrors;
+
+impl<S, B> Transform<S, ServiceRequest> for RenderErrors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RenderErrorsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RenderErrorsMiddleware { service }))
+    }
+}
+
+pub struct RenderErrorsMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RenderErrorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let response = self.service.call(req);
+        Box::pin(async move {
+            let response = response.await?;
+            let problem = match response.response().error() {
+                Some(error) => Problem::from(error),
+                None => return Ok(response.map_into_boxed_body()),
+            };
+            let mut rendered = render(response.request(), &problem);
+            // Keep the headers that describe the error, such as `Allow` on a 405.
+            for (name, value) in response.headers() {
+                if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+                    rendered.headers_mut().append(name.clone(), value.clone());
+                }
+            }
+            Ok(response.into_response(rendered))
+        })
+    }
+}
+
+/// Renders `problem` with the [`ErrorRenderer`] in the app data, if there is one.
+pub(crate) fn render(request: &HttpRequest, problem: &Problem) -> HttpResponse {
+    match request.app_data::<web::Data<ErrorRenderer>>() {
+        Some(renderer) => renderer.render(problem),
+        None => problem.render(),
+    }
+}
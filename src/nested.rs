@@ -0,0 +1,128 @@
+use crate::error::NotFound;
+use crate::rest_model::primary_key_condition;
+use crate::{permission, Action, Context, Hooks, Rest};
+use actix_web::web;
+use async_trait::async_trait;
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, Identity, Iterable, JoinType, ModelTrait,
+    PrimaryKeyToColumn, QueryFilter, QuerySelect, Related, Select, Value,
+};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+type Column<T> = <<T as Rest>::Entity as EntityTrait>::Column;
+
+type Scope<E> = Box<dyn Fn(Select<E>) -> Select<E>>;
+
+/// The rows of a nested resource that belong to the parent row named in the path.
+pub(crate) struct ParentScope<T: Rest> {
+    /// Narrows a query of the nested resource to the rows related to the parent.
+    scope: Scope<T::Entity>,
+    /// The foreign key columns of the nested resource, and the values that point them at
+    /// the parent. This is empty when the relation goes through a junction table.
+    pub keys: Vec<(Column<T>, Value)>,
+}
+
+impl<T: Rest> ParentScope<T> {
+    pub fn apply(&self, select: Select<T::Entity>) -> Select<T::Entity> {
+        (self.scope)(select)
+    }
+}
+
+/// Resolves the parent of a nested resource. Nested scopes register one of these as
+/// app data, so that handlers can find it.
+#[async_trait(?Send)]
+pub(crate) trait Parent<T: Rest> {
+    async fn resolve(&self, ctx: &Context<'_>) -> crate::Result<ParentScope<T>>;
+}
+
+/// The parent `P` of a resource `T` nested beneath it, whose primary key is read from
+/// the path segments of [`Rest::id_path`] under `scope`. The parent must be found by the
+/// [`Hooks::base_query`] of `P`, and be retrievable under its [`Rest::permission`].
+pub(crate) struct Nested<P, T> {
+    scope: String,
+    _pd: PhantomData<(P, T)>,
+}
+
+impl<P, T> Nested<P, T> {
+    pub fn new(scope: String) -> Self {
+        Self {
+            scope,
+            _pd: PhantomData,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<P, T> Parent<T> for Nested<P, T>
+where
+    P: Hooks,
+    T: Rest,
+    P::Entity: Related<T::Entity>,
+    <P::Entity as EntityTrait>::Model: sea_orm::IntoActiveModel<P::ActiveModel>,
+{
+    async fn resolve(&self, ctx: &Context<'_>) -> crate::Result<ParentScope<T>> {
+        let id = P::id_from_path(Some(&self.scope), ctx.request.match_info())?;
+        permission::authorize::<P>(ctx, Action::Retrieve).await?;
+        let parent = P::base_query(ctx)
+            .filter(primary_key_condition::<P::Entity>(id))
+            .one(ctx.db)
+            .await?
+            .ok_or_else(NotFound)?;
+        permission::authorize_object::<P>(ctx, Action::Retrieve, &parent).await?;
+
+        let keys = match <P::Entity as Related<T::Entity>>::via() {
+            Some(..) => vec![],
+            None => {
+                let relation = <P::Entity as Related<T::Entity>>::to();
+                columns(relation.from_col)
+                    .zip(columns(relation.to_col))
+                    .filter_map(|(from, to)| {
+                        let from = <P::Entity as EntityTrait>::Column::from_str(&from).ok()?;
+                        let to = Column::<T>::from_str(&to).ok()?;
+                        Some((to, parent.get(from)))
+                    })
+                    .collect()
+            }
+        };
+
+        // Like `parent.find_related(..)`, but applied to the base query of `T`.
+        let condition = <P::Entity as EntityTrait>::PrimaryKey::iter()
+            .map(PrimaryKeyToColumn::into_column)
+            .fold(Condition::all(), |condition, column| {
+                condition.add(column.eq(parent.get(column)))
+            });
+        Ok(ParentScope {
+            scope: Box::new(move |select| {
+                let select =
+                    select.join_rev(JoinType::InnerJoin, <P::Entity as Related<T::Entity>>::to());
+                let select = match <P::Entity as Related<T::Entity>>::via() {
+                    Some(via) => select.join_rev(JoinType::InnerJoin, via),
+                    None => select,
+                };
+                select.filter(condition.clone())
+            }),
+            keys,
+        })
+    }
+}
+
+fn columns(identity: Identity) -> impl Iterator<Item = String> {
+    match identity {
+        Identity::Unary(a) => vec![a],
+        Identity::Binary(a, b) => vec![a, b],
+        Identity::Ternary(a, b, c) => vec![a, b, c],
+    }
+    .into_iter()
+    .map(|iden| iden.to_string())
+}
+
+/// Resolves the parent of the resource `T`, if it is mounted beneath one.
+pub(crate) async fn resolve<T: Rest + 'static>(
+    ctx: &Context<'_>,
+) -> crate::Result<Option<ParentScope<T>>> {
+    match ctx.request.app_data::<web::Data<dyn Parent<T>>>() {
+        Some(parent) => Ok(Some(parent.resolve(ctx).await?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,40 @@
+use crate::error::UnknownOrdering;
+use crate::Rest;
+use sea_orm::{EntityTrait, IdenStatic, Iterable, Order, PrimaryKeyToColumn};
+
+pub(crate) type Column<T> = <<T as Rest>::Entity as EntityTrait>::Column;
+
+/// Resolves the ordering of a list request from the `?ordering=` query parameter, such
+/// as `-created_at,username`, falling back to [`Rest::default_ordering`].
+///
+/// Only the columns in [`Rest::orderable_columns`] may be requested. The primary key is
+/// always appended as a tiebreaker so that pages are stable.
+pub(crate) fn resolve<T: Rest>(requested: Option<&str>) -> crate::Result<Vec<(Column<T>, Order)>> {
+    let mut ordering = match requested.filter(|requested| !requested.is_empty()) {
+        Some(requested) => requested
+            .split(',')
+            .map(|field| {
+                let (name, order) = match field.strip_prefix('-') {
+                    Some(name) => (name, Order::Desc),
+                    None => (field, Order::Asc),
+                };
+                T::orderable_columns()
+                    .into_iter()
+                    .find(|column| column.as_str() == name)
+                    .map(|column| (column, order))
+                    .ok_or_else(|| UnknownOrdering(field))
+            })
+            .collect::<crate::Result<Vec<_>>>()?,
+        None => T::default_ordering(),
+    };
+    for key_col in <T::Entity as EntityTrait>::PrimaryKey::iter() {
+        let column = key_col.into_column();
+        if !ordering
+            .iter()
+            .any(|(ordered, _)| ordered.as_str() == column.as_str())
+        {
+            ordering.push((column, Order::Asc));
+        }
+    }
+    Ok(ordering)
+}
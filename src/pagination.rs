@@ -1,19 +1,356 @@
+use crate::cursor::{Cursor, CursorKey, Direction};
+use crate::error::{InvalidCursor, MissingCursorKey, PageOutOfRange};
+use crate::{value, Filter, Rest};
+use actix_web::{web, HttpRequest};
+use async_trait::async_trait;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    IdenStatic, ModelTrait, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    QueryTrait, Select, Statement,
+};
 use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+
+type Ordering<T> = [(<<T as Rest>::Entity as EntityTrait>::Column, Order)];
+
+/// A strategy for splitting the results of a list request into pages, chosen per
+/// resource by [`Rest::Pagination`].
+#[async_trait(?Send)]
+pub trait Paginate<T: Rest>: Serialize + Sized {
+    /// Fetches the requested page of a filtered query, which must be ordered by `ordering`.
+    /// The ordering always ends with the primary key, so it is total.
+    async fn paginate(
+        select: Select<T::Entity>,
+        ordering: &Ordering<T>,
+        filter: &T::Filter,
+        request: &HttpRequest,
+        db: &DatabaseConnection,
+    ) -> crate::Result<Self>;
+}
+
+/// How the total number of rows matching a list request is reported, chosen per
+/// resource by [`Rest::count_policy`].
+///
+/// An exact count runs a full `COUNT(*)`, which can dominate the latency of requests
+/// against very large tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CountPolicy {
+    /// Count the matching rows exactly.
+    Exact,
+    /// Do not count the rows at all, leaving `total` empty.
+    Skip,
+    /// Use the query planner's estimate of the number of rows, on Postgres. Other
+    /// databases fall back to an exact count.
+    Approximate,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LimitOffsetPagination<T> {
-    pub total: usize,
+    pub total: Option<usize>,
     pub items: Vec<T>,
+    pub next: Option<String>,
+    pub previous: Option<String>,
+}
+
+#[async_trait(?Send)]
+impl<T> Paginate<T> for LimitOffsetPagination<T::Repr>
+where
+    T: Rest,
+    <T::Entity as EntityTrait>::Model: Sync,
+{
+    async fn paginate(
+        select: Select<T::Entity>,
+        ordering: &Ordering<T>,
+        filter: &T::Filter,
+        request: &HttpRequest,
+        db: &DatabaseConnection,
+    ) -> crate::Result<Self> {
+        let limit = limit::<T>(filter);
+        let offset = filter.offset();
+        let total = count::<T>(&select, db).await?;
+        let (items, has_next) =
+            fetch::<T>(order::<T>(select, ordering, false), offset, limit, db).await?;
+        let next = has_next.then(|| {
+            page_url(
+                request,
+                &[
+                    ("limit", limit.to_string()),
+                    ("offset", (offset + limit).to_string()),
+                ],
+            )
+        });
+        let previous = (offset > 0).then(|| {
+            page_url(
+                request,
+                &[
+                    ("limit", limit.to_string()),
+                    ("offset", offset.saturating_sub(limit).to_string()),
+                ],
+            )
+        });
+        Ok(Self {
+            total,
+            items,
+            next,
+            previous,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PageNumberPagination<T> {
-    pub total: usize,
+    pub total: Option<usize>,
+    pub has_next: bool,
     pub items: Vec<T>,
 }
 
+#[async_trait(?Send)]
+impl<T> Paginate<T> for PageNumberPagination<T::Repr>
+where
+    T: Rest,
+    <T::Entity as EntityTrait>::Model: Sync,
+{
+    async fn paginate(
+        select: Select<T::Entity>,
+        ordering: &Ordering<T>,
+        filter: &T::Filter,
+        _request: &HttpRequest,
+        db: &DatabaseConnection,
+    ) -> crate::Result<Self> {
+        let limit = limit::<T>(filter);
+        let offset = filter
+            .page()
+            .checked_mul(limit)
+            .ok_or_else(PageOutOfRange)?;
+        let total = count::<T>(&select, db).await?;
+        let (items, has_next) =
+            fetch::<T>(order::<T>(select, ordering, false), offset, limit, db).await?;
+        Ok(Self {
+            total,
+            has_next,
+            items,
+        })
+    }
+}
+
+/// Keyset pagination, which pages from the last row seen rather than by offset and so
+/// stays fast however deep into a large table the client goes.
+///
+/// The `next` and `previous` cursors are opaque and signed with the [`CursorKey`] in
+/// the app data. Ordering columns are assumed not to contain `NULL`s.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CursorPagination<T> {
     pub items: Vec<T>,
-    pub cursor: String,
+    pub next: Option<String>,
+    pub previous: Option<String>,
+}
+
+#[async_trait(?Send)]
+impl<T> Paginate<T> for CursorPagination<T::Repr>
+where
+    T: Rest,
+    <T::Entity as EntityTrait>::Model: Sync,
+{
+    async fn paginate(
+        select: Select<T::Entity>,
+        ordering: &Ordering<T>,
+        filter: &T::Filter,
+        request: &HttpRequest,
+        db: &DatabaseConnection,
+    ) -> crate::Result<Self> {
+        let key = request
+            .app_data::<web::Data<CursorKey>>()
+            .ok_or_else(MissingCursorKey)?;
+        let limit = limit::<T>(filter);
+        let ordering_key = ordering
+            .iter()
+            .map(|(column, order)| match order {
+                Order::Desc => format!("-{}", column.as_str()),
+                _ => column.as_str().to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let cursor = filter
+            .cursor()
+            .map(|cursor| Cursor::decode(cursor, key))
+            .transpose()?;
+        let direction = cursor
+            .as_ref()
+            .map_or(Direction::Next, |cursor| cursor.direction);
+        let mut select = select;
+        if let Some(cursor) = &cursor {
+            if cursor.ordering != ordering_key || cursor.values.len() != ordering.len() {
+                return Err(InvalidCursor());
+            }
+            let values = ordering
+                .iter()
+                .zip(&cursor.values)
+                .map(|((column, _), raw)| {
+                    let column_type = column.def().get_column_type().clone();
+                    raw.as_deref()
+                        .and_then(|raw| value::parse(&column_type, raw))
+                        .ok_or_else(InvalidCursor)
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+            select = select.filter(keyset(ordering, &values, direction));
+        }
+        // Pages before the cursor are found by walking backwards from it.
+        let mut models = order::<T>(select, ordering, direction == Direction::Previous)
+            .limit(limit as u64 + 1)
+            .all(db)
+            .await?;
+        let has_more = models.len() > limit;
+        models.truncate(limit);
+        if direction == Direction::Previous {
+            models.reverse();
+        }
+
+        let cursor_from = |model: &<T::Entity as EntityTrait>::Model, direction| {
+            Cursor {
+                direction,
+                ordering: ordering_key.clone(),
+                values: ordering
+                    .iter()
+                    .map(|(column, _)| value::format(&model.get(*column)))
+                    .collect(),
+            }
+            .encode(key)
+        };
+        let (has_next, has_previous) = match direction {
+            Direction::Next => (has_more, cursor.is_some()),
+            Direction::Previous => (true, has_more),
+        };
+        let next = models
+            .last()
+            .filter(|_| has_next)
+            .map(|model| cursor_from(model, Direction::Next));
+        let previous = models
+            .first()
+            .filter(|_| has_previous)
+            .map(|model| cursor_from(model, Direction::Previous));
+
+        Ok(Self {
+            items: models.into_iter().map(From::from).collect(),
+            next,
+            previous,
+        })
+    }
+}
+
+/// The condition selecting rows strictly after (or before) the row with the given values
+/// of the ordering columns: `(a > x) OR (a = x AND b > y) OR ...`.
+fn keyset<C: ColumnTrait>(
+    ordering: &[(C, Order)],
+    values: &[sea_orm::Value],
+    direction: Direction,
+) -> Condition {
+    ordering.iter().zip(values).enumerate().fold(
+        Condition::any(),
+        |any, (i, ((column, order), value))| {
+            let ties = ordering[..i]
+                .iter()
+                .zip(values)
+                .fold(Condition::all(), |all, ((column, _), value)| {
+                    all.add(column.eq(value.clone()))
+                });
+            let ascending = matches!(order, Order::Asc) == (direction == Direction::Next);
+            let beyond = if ascending {
+                column.gt(value.clone())
+            } else {
+                column.lt(value.clone())
+            };
+            any.add(ties.add(beyond))
+        },
+    )
+}
+
+/// Counts the rows matched by a query according to [`Rest::count_policy`].
+async fn count<T: Rest>(
+    select: &Select<T::Entity>,
+    db: &DatabaseConnection,
+) -> crate::Result<Option<usize>>
+where
+    <T::Entity as EntityTrait>::Model: Sync,
+{
+    let backend = db.get_database_backend();
+    match T::count_policy() {
+        CountPolicy::Skip => Ok(None),
+        CountPolicy::Approximate if backend == DbBackend::Postgres => {
+            let statement = select.build(backend);
+            let explain = Statement {
+                sql: format!("EXPLAIN (FORMAT JSON) {}", statement.sql),
+                ..statement
+            };
+            let plan: serde_json::Value = match db.query_one(explain).await? {
+                Some(row) => row.try_get("", "QUERY PLAN")?,
+                None => return Ok(None),
+            };
+            Ok(plan[0]["Plan"]["Plan Rows"]
+                .as_f64()
+                .map(|rows| rows as usize))
+        }
+        CountPolicy::Exact | CountPolicy::Approximate => Ok(Some(select.clone().count(db).await?)),
+    }
+}
+
+/// Fetches a page of at most `limit` rows starting at `offset`, fetching one extra row
+/// to find out whether there is another page after it without having to count.
+async fn fetch<T: Rest>(
+    select: Select<T::Entity>,
+    offset: usize,
+    limit: usize,
+    db: &DatabaseConnection,
+) -> crate::Result<(Vec<T::Repr>, bool)> {
+    // Postgres takes a signed offset, and one that does not fit is the client's mistake.
+    if i64::try_from(offset).is_err() {
+        return Err(PageOutOfRange());
+    }
+    let mut models = select
+        .offset(offset as u64)
+        .limit((limit as u64).saturating_add(1))
+        .all(db)
+        .await?;
+    let has_next = models.len() > limit;
+    models.truncate(limit);
+    Ok((models.into_iter().map(From::from).collect(), has_next))
+}
+
+fn order<T: Rest>(
+    select: Select<T::Entity>,
+    ordering: &Ordering<T>,
+    reversed: bool,
+) -> Select<T::Entity> {
+    ordering.iter().fold(select, |select, (column, order)| {
+        let order = match (order, reversed) {
+            (Order::Asc, true) => Order::Desc,
+            (Order::Desc, true) => Order::Asc,
+            (order, _) => order.clone(),
+        };
+        select.order_by(*column, order)
+    })
+}
+
+/// The page size requested by the client, capped at [`Rest::max_limit`].
+fn limit<T: Rest>(filter: &T::Filter) -> usize {
+    let limit = filter.limit();
+    T::max_limit().map_or(limit, |max_limit| limit.min(max_limit))
+}
+
+/// The URL of the current request, with the given query parameters replaced.
+///
+/// The URL is relative, as the host of the request is chosen by the client and cannot be
+/// trusted to be a valid or an honest one.
+fn page_url(request: &HttpRequest, params: &[(&str, String)]) -> String {
+    let query = form_urlencoded::parse(request.query_string().as_bytes())
+        .filter(|(key, _)| params.iter().all(|(param, _)| key != param))
+        .chain(
+            params
+                .iter()
+                .map(|(param, value)| ((*param).into(), value.as_str().into())),
+        );
+    let query = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(query)
+        .finish();
+    format!("{}?{query}", request.path())
 }
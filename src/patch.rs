@@ -0,0 +1,71 @@
+use sea_orm::{ActiveValue, Value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A field of a partial update which distinguishes between being left out of the
+/// request body and being explicitly set to `null`.
+///
+/// Serde cannot tell these apart on its own, so `Patch` fields must be marked
+/// `#[serde(default)]`: absent fields then default to [`Patch::Absent`], while fields
+/// that are present deserialize as either [`Patch::Null`] or [`Patch::Value`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Patch<T> {
+    #[default]
+    Absent,
+    Null,
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Self::Absent)
+    }
+
+    /// Converts this patch into the `ActiveValue` of a nullable column, leaving the
+    /// column unchanged when the field was absent.
+    pub fn into_active_value(self) -> ActiveValue<Option<T>>
+    where
+        Option<T>: Into<Value>,
+    {
+        match self {
+            Self::Absent => ActiveValue::NotSet,
+            Self::Null => ActiveValue::Set(None),
+            Self::Value(value) => ActiveValue::Set(Some(value)),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(option: Option<T>) -> Self {
+        match option {
+            Some(value) => Self::Value(value),
+            None => Self::Null,
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Patch<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::deserialize(deserializer).map(Into::into)
+    }
+}
+
+impl<T> Serialize for Patch<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Absent | Self::Null => serializer.serialize_none(),
+            Self::Value(value) => serializer.serialize_some(value),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use crate::error::{Problem, UnsupportedMediaType};
+use actix_web::error::{JsonPayloadError, QueryPayloadError};
+use actix_web::{http::StatusCode, HttpRequest};
+use serde::de::{DeserializeOwned, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::error::Category;
+use std::cell::Cell;
+use std::fmt;
+use url::form_urlencoded;
+
+/// Converts an error from deserializing a JSON request body, naming the offending field
+/// if `body` is known.
+pub(crate) fn invalid_json(error: &serde_json::Error, body: Option<&[u8]>) -> crate::Error {
+    let message = message(error);
+    if error.classify() != Category::Data {
+        return Problem::new(StatusCode::BAD_REQUEST)
+            .with_detail(format!(
+                "Malformed JSON at line {} column {}: {message}",
+                error.line(),
+                error.column()
+            ))
+            .into();
+    }
+
+    let mut path = body
+        .map(|body| path(body, offset(body, error.line(), error.column())))
+        .unwrap_or_default();
+    if let Some(field) = quoted_field(&message, "missing field `") {
+        path.push(Segment::Key(field.to_owned()));
+    } else if let Some(field) = quoted_field(&message, "unknown field `") {
+        // The unknown key has just been read, so it may already end the path.
+        if path.last() != Some(&Segment::Key(field.to_owned())) {
+            path.push(Segment::Key(field.to_owned()));
+        }
+    }
+
+    let problem = Problem::new(StatusCode::BAD_REQUEST);
+    if path.is_empty() {
+        return problem
+            .with_detail(format!("Invalid request body: {message}"))
+            .into();
+    }
+    let path = render(&path);
+    problem
+        .with_detail(format!("Invalid value for {path}: {message}"))
+        .with_error(path, vec![message])
+        .into()
+}
+
+/// Converts the errors of reading a JSON body that are the client's fault, unless the
+/// [`web::JsonConfig`](actix_web::web::JsonConfig) has already handled them.
+pub(crate) fn json_error(error: actix_web::Error) -> actix_web::Error {
+    match error.as_error::<JsonPayloadError>() {
+        Some(JsonPayloadError::Deserialize(error)) => invalid_json(error, None).into(),
+        Some(JsonPayloadError::ContentType) => UnsupportedMediaType().into(),
+        _ => error,
+    }
+}
+
+/// Handles the errors of [`web::Query`] extractors for `F`, naming the parameter that `F`
+/// could not be deserialized from.
+pub(crate) fn query_error<F: DeserializeOwned>(
+    error: QueryPayloadError,
+    request: &HttpRequest,
+) -> actix_web::Error {
+    let message = error.to_string();
+    let message = message
+        .strip_prefix("Query deserialize error: ")
+        .unwrap_or(&message)
+        .to_owned();
+    let problem = Problem::new(StatusCode::BAD_REQUEST);
+    let problem = match culprit::<F>(request.query_string()) {
+        Some(key) => problem
+            .with_detail(format!("Invalid query parameter {key}: {message}"))
+            .with_error(key, vec![message]),
+        None => problem.with_detail(format!("Invalid query string: {message}")),
+    };
+    crate::Error::from(problem).into()
+}
+
+/// Finds the parameter that the query string could not be deserialized at, by reading it
+/// once more while counting the parameters read. Errors that no parameter caused, such as
+/// a missing one, have no culprit.
+fn culprit<F: DeserializeOwned>(query: &str) -> Option<String> {
+    let pairs = form_urlencoded::parse(query.as_bytes());
+    let progress = Progress::default();
+    let deserializer = Counted {
+        inner: serde_urlencoded::Deserializer::new(pairs),
+        progress: &progress,
+    };
+    F::deserialize(deserializer).err()?;
+    let (key, _) = pairs.clone().nth(progress.failed.get()?)?;
+    Some(key.into_owned())
+}
+
+/// How far reading a query string got.
+#[derive(Default)]
+struct Progress {
+    /// The number of parameters read.
+    read: Cell<usize>,
+    /// Whether every parameter has been read.
+    done: Cell<bool>,
+    /// The index of the parameter that reading failed at.
+    failed: Cell<Option<usize>>,
+}
+
+impl Progress {
+    fn next<T, E>(&self, next: &Result<Option<T>, E>) {
+        match next {
+            Ok(Some(_)) => self.read.set(self.read.get() + 1),
+            Ok(None) => self.done.set(true),
+            Err(_) => self.failed.set(Some(self.read.get())),
+        }
+    }
+
+    /// Blames the parameter read last for an error of the visitor, unless the error came
+    /// after every parameter was read.
+    fn visited<T, E>(&self, visited: &Result<T, E>) {
+        if visited.is_err() && self.failed.get().is_none() && !self.done.get() {
+            self.failed.set(self.read.get().checked_sub(1));
+        }
+    }
+}
+
+/// Wraps the deserializer of a query string, and the visitor and accessors it is read
+/// with, to record its [`Progress`].
+struct Counted<'a, T> {
+    inner: T,
+    progress: &'a Progress,
+}
+
+impl<'a, T> Counted<'a, T> {
+    fn wrap<U>(&self, inner: U) -> Counted<'a, U> {
+        Counted {
+            inner,
+            progress: self.progress,
+        }
+    }
+}
+
+impl<'de, 'a, D: Deserializer<'de>> Deserializer<'de> for Counted<'a, D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_seq(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        self.inner.deserialize_unit(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit_struct newtype_struct tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+impl<'de, 'a, V: Visitor<'de>> Visitor<'de> for Counted<'a, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        let map = self.wrap(map);
+        let visited = self.inner.visit_map(map);
+        self.progress.visited(&visited);
+        visited
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        let seq = self.wrap(seq);
+        let visited = self.inner.visit_seq(seq);
+        self.progress.visited(&visited);
+        visited
+    }
+}
+
+impl<'de, 'a, A: MapAccess<'de>> MapAccess<'de> for Counted<'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        let key = self.inner.next_key_seed(seed);
+        self.progress.next(&key);
+        key
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        let value = self.inner.next_value_seed(seed);
+        if value.is_err() {
+            let read = self.progress.read.get();
+            self.progress.failed.set(read.checked_sub(1));
+        }
+        value
+    }
+}
+
+impl<'de, 'a, A: SeqAccess<'de>> SeqAccess<'de> for Counted<'a, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let element = self.inner.next_element_seed(seed);
+        self.progress.next(&element);
+        element
+    }
+}
+
+/// The error message of `error`, without the position that serde_json appends to it.
+fn message(error: &serde_json::Error) -> String {
+    let message = error.to_string();
+    match message.rfind(" at line ") {
+        Some(end) => message[..end].to_owned(),
+        None => message,
+    }
+}
+
+fn quoted_field<'a>(message: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = message.strip_prefix(prefix)?;
+    Some(&rest[..rest.find('`')?])
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// The byte offset of a 1-based line and column as reported by serde_json.
+fn offset(body: &[u8], line: usize, column: usize) -> usize {
+    let start = body
+        .split_inclusive(|byte| *byte == b'\n')
+        .take(line.saturating_sub(1))
+        .map(<[u8]>::len)
+        .sum::<usize>();
+    (start + column).min(body.len())
+}
+
+/// The path to the value that the JSON `body` was being read at when `end` was reached.
+fn path(body: &[u8], end: usize) -> Vec<Segment> {
+    enum Frame {
+        Object(Option<String>),
+        Array(usize),
+    }
+
+    let mut stack = vec![];
+    let mut expecting_key = false;
+    let mut bytes = body[..end].iter().copied();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'{' => {
+                stack.push(Frame::Object(None));
+                expecting_key = true;
+            }
+            b'[' => stack.push(Frame::Array(0)),
+            b'}' | b']' => {
+                stack.pop();
+                expecting_key = false;
+            }
+            b',' => match stack.last_mut() {
+                Some(Frame::Object(key)) => {
+                    *key = None;
+                    expecting_key = true;
+                }
+                Some(Frame::Array(index)) => *index += 1,
+                None => {}
+            },
+            b'"' => {
+                let mut raw = vec![b'"'];
+                while let Some(byte) = bytes.next() {
+                    raw.push(byte);
+                    match byte {
+                        b'\\' => raw.extend(bytes.next()),
+                        b'"' => break,
+                        _ => {}
+                    }
+                }
+                if expecting_key {
+                    if let Some(Frame::Object(key)) = stack.last_mut() {
+                        *key = serde_json::from_slice(&raw).ok();
+                    }
+                    expecting_key = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    stack
+        .into_iter()
+        .filter_map(|frame| match frame {
+            Frame::Object(key) => key.map(Segment::Key),
+            Frame::Array(index) => Some(Segment::Index(index)),
+        })
+        .collect()
+}
+
+/// Renders a path like `tags[0].name`.
+fn render(path: &[Segment]) -> String {
+    let mut rendered = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) if rendered.is_empty() => rendered.push_str(key),
+            Segment::Key(key) => {
+                rendered.push('.');
+                rendered.push_str(key);
+            }
+            Segment::Index(index) => rendered.push_str(&format!("[{index}]")),
+        }
+    }
+    rendered
+}
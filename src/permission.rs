@@ -0,0 +1,198 @@
+use crate::error::{Forbidden, Unauthorized};
+use crate::extensions::Identity;
+use crate::{value, Action, Context, Rest};
+use actix_web::HttpMessage;
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait};
+
+type Model<T> = <<T as Rest>::Entity as EntityTrait>::Model;
+
+/// Decides whether a request may perform an action of the resource `T`, as declared by
+/// [`Rest::permission`].
+///
+/// [`has_permission`](Self::has_permission) is checked before every action, and
+/// [`has_object_permission`](Self::has_object_permission) once the row that an action
+/// reads or writes has been found. Lists are only checked with the former. Denied
+/// requests are answered with 401 if they are anonymous and 403 otherwise.
+///
+/// Permissions are combined with [`And`], [`Or`] and [`Not`].
+#[async_trait(?Send)]
+pub trait Permission<T: Rest> {
+    async fn has_permission(&self, _ctx: &Context<'_>, _action: Action) -> crate::Result<bool> {
+        Ok(true)
+    }
+
+    async fn has_object_permission(
+        &self,
+        _ctx: &Context<'_>,
+        _action: Action,
+        _model: &Model<T>,
+    ) -> crate::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Allows every request. This is the default permission of a resource.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllowAny;
+
+#[async_trait(?Send)]
+impl<T: Rest> Permission<T> for AllowAny {}
+
+/// Allows requests made by an authenticated user.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IsAuthenticated;
+
+#[async_trait(?Send)]
+impl<T: Rest> Permission<T> for IsAuthenticated {
+    async fn has_permission(&self, ctx: &Context<'_>, _action: Action) -> crate::Result<bool> {
+        Ok(ctx.request.extensions().contains::<Identity>())
+    }
+}
+
+/// Allows requests with safe methods, such as `GET`, which only read rows.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReadOnly;
+
+#[async_trait(?Send)]
+impl<T: Rest> Permission<T> for ReadOnly {
+    async fn has_permission(&self, ctx: &Context<'_>, _action: Action) -> crate::Result<bool> {
+        Ok(ctx.request.method().is_safe())
+    }
+
+    async fn has_object_permission(
+        &self,
+        ctx: &Context<'_>,
+        _action: Action,
+        _model: &Model<T>,
+    ) -> crate::Result<bool> {
+        Ok(ctx.request.method().is_safe())
+    }
+}
+
+/// Allows access to the rows whose `column` holds the id of the current user, as reported
+/// by [`Authenticate::user_id`](crate::auth::Authenticate::user_id).
+///
+/// Actions that are not on a single row, such as creating one, are allowed.
+#[derive(Clone, Copy, Debug)]
+pub struct IsOwner<C>(pub C);
+
+#[async_trait(?Send)]
+impl<T, C> Permission<T> for IsOwner<C>
+where
+    T: Rest,
+    T::Entity: EntityTrait<Column = C>,
+    C: ColumnTrait,
+{
+    async fn has_object_permission(
+        &self,
+        ctx: &Context<'_>,
+        _action: Action,
+        model: &Model<T>,
+    ) -> crate::Result<bool> {
+        let extensions = ctx.request.extensions();
+        let user_id = extensions
+            .get::<Identity>()
+            .and_then(|identity| identity.id.as_ref());
+        Ok(user_id.is_some_and(|id| value::format(&model.get(self.0)).as_ref() == Some(id)))
+    }
+}
+
+/// Allows requests that both permissions allow.
+#[derive(Clone, Copy, Debug)]
+pub struct And<A, B>(pub A, pub B);
+
+#[async_trait(?Send)]
+impl<T: Rest, A: Permission<T>, B: Permission<T>> Permission<T> for And<A, B> {
+    async fn has_permission(&self, ctx: &Context<'_>, action: Action) -> crate::Result<bool> {
+        Ok(self.0.has_permission(ctx, action).await? && self.1.has_permission(ctx, action).await?)
+    }
+
+    async fn has_object_permission(
+        &self,
+        ctx: &Context<'_>,
+        action: Action,
+        model: &Model<T>,
+    ) -> crate::Result<bool> {
+        Ok(self.0.has_object_permission(ctx, action, model).await?
+            && self.1.has_object_permission(ctx, action, model).await?)
+    }
+}
+
+/// Allows requests that either permission allows.
+///
+/// A row is allowed by a permission only if the request is as well, so that a row is not
+/// let through by a permission whose request check failed.
+#[derive(Clone, Copy, Debug)]
+pub struct Or<A, B>(pub A, pub B);
+
+#[async_trait(?Send)]
+impl<T: Rest, A: Permission<T>, B: Permission<T>> Permission<T> for Or<A, B> {
+    async fn has_permission(&self, ctx: &Context<'_>, action: Action) -> crate::Result<bool> {
+        Ok(self.0.has_permission(ctx, action).await? || self.1.has_permission(ctx, action).await?)
+    }
+
+    async fn has_object_permission(
+        &self,
+        ctx: &Context<'_>,
+        action: Action,
+        model: &Model<T>,
+    ) -> crate::Result<bool> {
+        Ok((self.0.has_permission(ctx, action).await?
+            && self.0.has_object_permission(ctx, action, model).await?)
+            || (self.1.has_permission(ctx, action).await?
+                && self.1.has_object_permission(ctx, action, model).await?))
+    }
+}
+
+/// Allows the requests that a permission denies.
+///
+/// Only requests are negated. Every row is allowed, as a permission that does not check
+/// rows allows them all, and negating that would deny every row.
+#[derive(Clone, Copy, Debug)]
+pub struct Not<A>(pub A);
+
+#[async_trait(?Send)]
+impl<T: Rest, A: Permission<T>> Permission<T> for Not<A> {
+    async fn has_permission(&self, ctx: &Context<'_>, action: Action) -> crate::Result<bool> {
+        Ok(!self.0.has_permission(ctx, action).await?)
+    }
+}
+
+/// Fails unless the permission of `T` allows `action`.
+pub(crate) async fn authorize<T: Rest>(ctx: &Context<'_>, action: Action) -> crate::Result<()> {
+    let allowed = T::permission().has_permission(ctx, action).await?;
+    deny_unless(ctx, allowed)
+}
+
+/// Fails unless the permission of `T` allows `action` on `model`.
+pub(crate) async fn authorize_object<T: Rest>(
+    ctx: &Context<'_>,
+    action: Action,
+    model: &Model<T>,
+) -> crate::Result<()> {
+    let allowed = allows_object::<T>(ctx, action, model).await?;
+    deny_unless(ctx, allowed)
+}
+
+/// Whether the permission of `T` allows `action` on `model`, for rows that are left out
+/// of a response rather than refused.
+pub(crate) async fn allows_object<T: Rest>(
+    ctx: &Context<'_>,
+    action: Action,
+    model: &Model<T>,
+) -> crate::Result<bool> {
+    T::permission()
+        .has_object_permission(ctx, action, model)
+        .await
+}
+
+fn deny_unless(ctx: &Context<'_>, allowed: bool) -> crate::Result<()> {
+    if allowed {
+        Ok(())
+    } else if ctx.request.extensions().contains::<Identity>() {
+        Err(Forbidden())
+    } else {
+        Err(Unauthorized("Authentication required"))
+    }
+}
@@ -1,29 +1,62 @@
-use crate::pagination::PageNumberPagination;
-use crate::{extractors, middleware};
-use crate::{Filter, Rest};
+use crate::error::{MethodNotAllowed, NotFound};
+use crate::nested::{self, Nested, Parent, ParentScope};
+use crate::pagination::Paginate;
+use crate::shape::Shape;
+use crate::{expand, payload, traits, value};
+use crate::{extractors, middleware, ordering};
+use crate::{permission, Action, Actions, Context, Filter, Hooks, Rest};
 use actix_web::{
     body::BoxBody,
-    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
-    error,
-    http::StatusCode,
-    web, Error, HttpRequest, HttpResponse, Scope,
+    dev::{ResourceDef, ServiceFactory, ServiceRequest, ServiceResponse},
+    guard,
+    http::header::{self, HeaderValue},
+    http::{Method, StatusCode},
+    web, Error, FromRequest, Handler, HttpRequest, HttpResponse, Resource, Responder, Route, Scope,
 };
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use sea_orm::{
-    sea_query::IntoValueTuple, ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
-    Iterable, PaginatorTrait, PrimaryKeyToColumn, PrimaryKeyTrait, QueryFilter,
+    sea_query::{sea_value_to_json_value, IntoValueTuple},
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityName, EntityTrait,
+    IdenStatic, IntoActiveModel, Iterable, ModelTrait, PrimaryKeyToColumn, PrimaryKeyTrait,
+    QueryFilter, Related, Select,
 };
 use serde::de::DeserializeOwned;
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use std::future::ready;
 use std::marker::PhantomData;
+use std::slice;
+use std::sync::Arc;
+
+type PrimaryKeyValue<T> =
+    <<<T as Rest>::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType;
+
+type Model<T> = <<T as Rest>::Entity as EntityTrait>::Model;
+
+type Configure = Box<dyn Fn(&mut web::ServiceConfig)>;
+
+/// A custom action, served together with the other custom actions of the same name.
+struct Custom {
+    name: &'static str,
+    detail: bool,
+    method: Method,
+    route: Box<dyn Fn(Route) -> Route>,
+}
 
 pub struct RestModel<T> {
     _pd: PhantomData<T>,
     path: String,
+    nested: Vec<Configure>,
+    actions: Actions,
+    custom: Vec<Custom>,
+    new_alias: bool,
 }
 
 impl<T> RestModel<T>
 where
-    T: Rest + 'static,
-    <T::Entity as EntityTrait>::Model: IntoActiveModel<T::ActiveModel> + Send + Sync,
+    T: Hooks + 'static,
+    <T::Entity as EntityTrait>::Model:
+        IntoActiveModel<T::ActiveModel> + DeserializeOwned + Send + Sync,
     <<T::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType:
         DeserializeOwned + Clone,
 {
@@ -31,9 +64,164 @@ where
         Self {
             _pd: PhantomData,
             path: path.as_ref().to_owned(),
+            nested: vec![],
+            actions: Actions::ALL,
+            custom: vec![],
+            new_alias: false,
         }
     }
 
+    /// Also creates rows at `POST /resource/new`, as well as at `POST /resource`.
+    pub fn with_new_alias(mut self) -> Self {
+        self.new_alias = true;
+        self
+    }
+
+    /// Exposes only `actions`, answering the methods of the others with 405.
+    pub fn actions(mut self, actions: Actions) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    /// Stops exposing `action`, answering its method with 405.
+    pub fn without(mut self, action: Action) -> Self {
+        self.actions = self.actions.without(action);
+        self
+    }
+
+    /// Mounts `child` beneath each row of this resource, so that `/posts` may nest
+    /// `/comments` at `/posts/{posts_id}/comments`. The nested resource only reaches the
+    /// rows related to its parent, and responds with 404 if the parent does not exist.
+    pub fn nest<C>(mut self, child: RestModel<C>) -> Self
+    where
+        C: Hooks + 'static,
+        T::Entity: Related<C::Entity>,
+        <C::Entity as EntityTrait>::Model:
+            IntoActiveModel<C::ActiveModel> + DeserializeOwned + Send + Sync,
+        <<C::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType:
+            DeserializeOwned + Clone,
+    {
+        let scope = self
+            .path
+            .trim_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        let path = T::id_path(Some(&scope)) + &child.path;
+        let parent: Arc<dyn Parent<C>> = Arc::new(Nested::<T, C>::new(scope));
+        self.nested.push(Box::new(move |config| {
+            config.service(child.scope(&path, Some(parent.clone())));
+        }));
+        self
+    }
+
+    /// Mounts a read-only list of the rows of `C` related to each row of this resource,
+    /// named for the table of `C`, such as `/users/{users_id}/posts`. The list is filtered,
+    /// ordered and paginated just like the top-level list of `C`.
+    pub fn related<C>(self) -> Self
+    where
+        C: Hooks + 'static,
+        T::Entity: Related<C::Entity>,
+        <C::Entity as EntityTrait>::Model:
+            IntoActiveModel<C::ActiveModel> + DeserializeOwned + Send + Sync,
+        <<C::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType:
+            DeserializeOwned + Clone,
+    {
+        let path = format!("/{}", C::Entity::default().table_name());
+        self.nest(RestModel::<C>::new(path).actions(Action::List.into()))
+    }
+
+    /// Adds an action on each row, such as `POST /posts/{id}/publish`.
+    ///
+    /// `handler` is called with the row and the extractors in `Args`, once the permission
+    /// of the resource allows [`Action::Custom`] with this name on the row. Rows that this
+    /// request cannot reach respond with 404, just like the built-in actions.
+    ///
+    /// An action may be added once for each method that it answers.
+    pub fn detail_action<F, Args>(mut self, method: Method, name: &'static str, handler: F) -> Self
+    where
+        F: Handler<(Model<T>, Args)>,
+        F::Output: Responder + 'static,
+        Args: FromRequest + 'static,
+    {
+        let action = Action::Custom(name);
+        let route = move |route: Route| {
+            let handler = handler.clone();
+            route.to(
+                move |request: HttpRequest,
+                      id: extractors::PrimaryKey<T>,
+                      db: web::Data<DatabaseConnection>,
+                      args: Args| {
+                    let handler = handler.clone();
+                    async move {
+                        let ctx = Context {
+                            request: &request,
+                            db: &db,
+                        };
+                        permission::authorize::<T>(&ctx, action).await?;
+                        let parent = nested::resolve::<T>(&ctx).await?;
+                        let model = Self::find(&ctx, &parent, id.clone())
+                            .await?
+                            .ok_or_else(NotFound)?;
+                        permission::authorize_object::<T>(&ctx, action, &model).await?;
+                        Ok::<_, crate::Error>(handler.call((model, args)).await)
+                    }
+                },
+            )
+        };
+        self.custom.push(Custom {
+            name,
+            detail: true,
+            method,
+            route: Box::new(route),
+        });
+        self
+    }
+
+    /// Adds an action on the collection, such as `GET /users/me`.
+    ///
+    /// `handler` is called with the extractors in `Args`, once the permission of the
+    /// resource allows [`Action::Custom`] with this name.
+    ///
+    /// An action may be added once for each method that it answers.
+    pub fn collection_action<F, Args>(
+        mut self,
+        method: Method,
+        name: &'static str,
+        handler: F,
+    ) -> Self
+    where
+        F: Handler<Args>,
+        F::Output: Responder + 'static,
+        Args: FromRequest + 'static,
+    {
+        let action = Action::Custom(name);
+        let route = move |route: Route| {
+            let handler = handler.clone();
+            route.to(
+                move |request: HttpRequest, db: web::Data<DatabaseConnection>, args: Args| {
+                    let handler = handler.clone();
+                    async move {
+                        let ctx = Context {
+                            request: &request,
+                            db: &db,
+                        };
+                        permission::authorize::<T>(&ctx, action).await?;
+                        Ok::<_, crate::Error>(handler.call(args).await)
+                    }
+                },
+            )
+        };
+        self.custom.push(Custom {
+            name,
+            detail: false,
+            method,
+            route: Box::new(route),
+        });
+        self
+    }
+
     // TODO: is this the best way to write this return type?
     pub fn as_service(
         &self,
@@ -45,37 +233,103 @@ where
             Error = Error,
             InitError = (),
         >,
+    > {
+        self.scope(&self.path, None).wrap(middleware::RenderErrors)
+    }
+
+    fn scope(
+        &self,
+        path: &str,
+        parent: Option<Arc<dyn Parent<T>>>,
+    ) -> Scope<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<BoxBody>,
+            Error = Error,
+            InitError = (),
+        >,
     > {
         let id_path = T::id_path(None);
-        web::scope(&self.path)
-            .route("", web::get().to(Self::list))
-            .route("/new", web::post().to(Self::create))
-            .service(
-                web::resource(&id_path)
-                    .wrap(middleware::PrimaryKey::<T>::default())
-                    .route(web::get().to(Self::get)),
-            )
-            .service(
-                web::resource(&id_path)
-                    .wrap(middleware::PrimaryKey::<T>::default())
-                    .route(web::delete().to(Self::delete)),
-            )
-            .service(
-                web::resource(&id_path)
-                    .wrap(middleware::PrimaryKey::<T>::default())
-                    .route(web::patch().to(Self::update)),
-            )
-            .service(
-                web::resource(&id_path)
-                    .wrap(middleware::PrimaryKey::<T>::default())
-                    .route(web::put().to(Self::replace)),
-            )
+        let mut scope = web::scope(path)
+            .app_data(web::QueryConfig::default().error_handler(payload::query_error::<T::Filter>));
+        if let Some(parent) = parent {
+            scope = scope.app_data(web::Data::from(parent));
+        }
+        let mut scope = scope
+            .configure(|config| {
+                for nest in &self.nested {
+                    nest(config);
+                }
+            })
+            .service(self.resource(
+                "",
+                vec![
+                    (Action::List, route(&Method::GET).to(Self::list)),
+                    (Action::Create, web::post().to(Self::create)),
+                ],
+            ));
+        if self.new_alias && self.actions.contains(Action::Create) {
+            scope = scope.route("/new", web::post().to(Self::create));
+        }
+        let detail = vec![
+            (Action::Retrieve, route(&Method::GET).to(Self::get)),
+            (Action::Delete, web::delete().to(Self::delete)),
+            (Action::Update, web::patch().to(Self::update)),
+            (Action::Replace, web::put().to(Self::replace)),
+        ];
+        let mut custom: Vec<(&'static str, bool, Vec<&Custom>)> = vec![];
+        for action in &self.custom {
+            match custom
+                .iter_mut()
+                .find(|(name, detail, _)| *name == action.name && *detail == action.detail)
+            {
+                Some((_, _, actions)) => actions.push(action),
+                None => custom.push((action.name, action.detail, vec![action])),
+            }
+        }
+        for (name, detail, actions) in custom {
+            let path = match detail {
+                true => format!("{id_path}/{name}"),
+                false => format!("/{name}"),
+            };
+            let resource = actions
+                .iter()
+                .fold(web::resource(path), |resource, action| {
+                    resource.route((action.route)(route(&action.method)))
+                });
+            let resource = allow(
+                resource,
+                actions.iter().map(|action| action.method.clone()).collect(),
+            );
+            scope = match detail {
+                true => scope.service(resource.wrap(middleware::PrimaryKey::<T>::default())),
+                false => scope.service(resource),
+            };
+        }
+        // The detail resource is registered even without any detail actions, so that
+        // requests to it are answered with 405 rather than 404.
+        scope.service(
+            self.resource(&id_path, detail)
+                .wrap(middleware::PrimaryKey::<T>::default()),
+        )
+    }
+
+    /// A resource serving the routes of the exposed actions, which answers every other
+    /// method with 405 and the methods it allows.
+    fn resource(&self, path: &str, routes: Vec<(Action, Route)>) -> Resource {
+        let (allowed, routes): (Vec<_>, Vec<_>) = routes
+            .into_iter()
+            .filter(|(action, _)| self.actions.contains(*action))
+            .unzip();
+        let resource = routes
+            .into_iter()
+            .fold(web::resource(path), Resource::route);
+        let allowed = allowed.iter().filter_map(|action| action.method());
+        allow(resource, allowed.collect())
     }
 
-    fn set_primary_key(
-        primary_key: <<T::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType,
-        active_model: &mut T::ActiveModel,
-    ) {
+    fn set_primary_key(primary_key: PrimaryKeyValue<T>, active_model: &mut T::ActiveModel) {
         let pk_columns =
             <T::Entity as EntityTrait>::PrimaryKey::iter().map(PrimaryKeyToColumn::into_column);
         let pk_values = primary_key.into_value_tuple();
@@ -84,82 +338,325 @@ where
         }
     }
 
+    /// Points the foreign keys of a nested resource at its parent.
+    fn set_parent_keys(parent: &Option<ParentScope<T>>, active_model: &mut T::ActiveModel) {
+        for (column, value) in parent.iter().flat_map(|parent| parent.keys.iter()) {
+            active_model.set(*column, value.clone());
+        }
+    }
+
+    /// The row that `active_model` is about to be written as, so that it can be checked
+    /// before it is: `existing` with the columns that `active_model` sets, or a new row in
+    /// which each column left to the database holds a placeholder of its type.
+    fn pending(
+        active_model: &T::ActiveModel,
+        existing: Option<&Model<T>>,
+    ) -> crate::Result<Model<T>> {
+        let columns = <T::Entity as EntityTrait>::Column::iter();
+        if let Some(existing) = existing {
+            let mut model = existing.clone();
+            for column in columns {
+                if let Some(value) = active_model.get(column).into_value() {
+                    model.set(column, value);
+                }
+            }
+            return Ok(model);
+        }
+        let row = columns
+            .map(|column| {
+                let value = active_model
+                    .get(column)
+                    .into_value()
+                    .or_else(|| value::placeholder(column.def().get_column_type()));
+                let json = value.map_or(Json::Null, |value| sea_value_to_json_value(&value));
+                (column.as_str().to_owned(), json)
+            })
+            .collect();
+        Ok(serde_json::from_value(Json::Object(row))?)
+    }
+
+    /// The rows reachable by this request: those of the base query, narrowed to the rows
+    /// related to the parent of a nested resource.
+    fn select(ctx: &Context<'_>, parent: &Option<ParentScope<T>>) -> Select<T::Entity> {
+        let select = T::base_query(ctx);
+        match parent {
+            Some(parent) => parent.apply(select),
+            None => select,
+        }
+    }
+
+    /// Finds the row with the given primary key, if it is reachable by this request.
+    async fn find(
+        ctx: &Context<'_>,
+        parent: &Option<ParentScope<T>>,
+        primary_key: PrimaryKeyValue<T>,
+    ) -> crate::Result<Option<Model<T>>> {
+        Ok(Self::select(ctx, parent)
+            .filter(primary_key_condition::<T::Entity>(primary_key))
+            .one(ctx.db)
+            .await?)
+    }
+
     async fn get(
+        request: HttpRequest,
         id: extractors::PrimaryKey<T>,
+        shape: web::Query<Shape>,
         db: web::Data<DatabaseConnection>,
-    ) -> crate::Result<web::Json<T::Repr>> {
-        T::Entity::find_by_id(id.clone())
-            .one(&**db)
+    ) -> crate::Result<web::Json<Json>> {
+        let ctx = Context {
+            request: &request,
+            db: &db,
+        };
+        permission::authorize::<T>(&ctx, Action::Retrieve).await?;
+        let parent = nested::resolve::<T>(&ctx).await?;
+        // Every column is loaded, even if the response leaves some out, since the
+        // permission may depend on any of them.
+        let model = Self::find(&ctx, &parent, id.clone())
             .await?
-            .map(From::from)
-            .map(web::Json)
-            .ok_or_else(|| error::ErrorNotFound("Not found").into())
+            .ok_or_else(NotFound)?;
+        permission::authorize_object::<T>(&ctx, Action::Retrieve, &model).await?;
+        let mut body = serde_json::to_value(T::Repr::from(model))?;
+        expand::expand::<T>(slice::from_mut(&mut body), shape.expand.as_deref(), &ctx).await?;
+        shape.prune(&mut body);
+        Ok(web::Json(body))
     }
 
     async fn delete(
+        request: HttpRequest,
         id: extractors::PrimaryKey<T>,
         db: web::Data<DatabaseConnection>,
     ) -> crate::Result<HttpResponse> {
-        T::Entity::delete_by_id(id.clone()).exec(&**db).await?;
+        let ctx = Context {
+            request: &request,
+            db: &db,
+        };
+        permission::authorize::<T>(&ctx, Action::Delete).await?;
+        let parent = nested::resolve::<T>(&ctx).await?;
+        let mut active_model = T::ActiveModel::default();
+        Self::set_primary_key(id.clone(), &mut active_model);
+        let deleted = match Self::find(&ctx, &parent, id.clone()).await? {
+            Some(model) => {
+                permission::authorize_object::<T>(&ctx, Action::Delete, &model).await?;
+                T::before_delete(&ctx, &mut active_model).await?;
+                let result = T::perform_delete(&ctx, active_model.clone()).await?;
+                result.rows_affected > 0
+            }
+            None => false,
+        };
+        if deleted {
+            T::after_delete(&ctx, &active_model).await?;
+        } else if !T::idempotent_delete() {
+            return Err(NotFound());
+        }
         Ok(HttpResponse::new(StatusCode::NO_CONTENT))
     }
 
     async fn create(
-        body: web::Json<T::Create>,
+        request: HttpRequest,
+        body: extractors::Body<T::Create>,
         db: web::Data<DatabaseConnection>,
-    ) -> crate::Result<web::Json<T::Repr>> {
-        Ok(web::Json(
-            T::Entity::insert(body.clone().into_active_model())
-                .exec_with_returning(&**db)
-                .await?
-                .into(),
-        ))
+    ) -> crate::Result<HttpResponse> {
+        let ctx = Context {
+            request: &request,
+            db: &db,
+        };
+        permission::authorize::<T>(&ctx, Action::Create).await?;
+        let parent = nested::resolve::<T>(&ctx).await?;
+        traits::validate(&*body, &db).await?;
+        let mut active_model = body.clone().into_active_model();
+        Self::set_parent_keys(&parent, &mut active_model);
+        T::before_create(&ctx, &mut active_model).await?;
+        let pending = Self::pending(&active_model, None)?;
+        permission::authorize_object::<T>(&ctx, Action::Create, &pending).await?;
+        let model = T::perform_create(&ctx, active_model).await?;
+        T::after_create(&ctx, &model).await?;
+        let mut response = HttpResponse::Created();
+        if let Some(location) = Self::location(&request, &model) {
+            response.insert_header((header::LOCATION, location));
+        }
+        Ok(response.json(T::Repr::from(model)))
+    }
+
+    /// The path of a row created by `request`, which is the path of the collection it was
+    /// posted to followed by [`Rest::id_path`], with each primary key column in the path
+    /// segment that [`Rest::id_from_path`] reads it from.
+    fn location(request: &HttpRequest, model: &Model<T>) -> Option<String> {
+        let collection = request.path().trim_end_matches('/');
+        let collection = match request.match_pattern() {
+            Some(pattern) if pattern.ends_with("/new") => collection.strip_suffix("/new")?,
+            _ => collection,
+        };
+        let segments = <T::Entity as EntityTrait>::PrimaryKey::iter()
+            .map(|key| {
+                let column = key.into_column();
+                let value = value::format(&model.get(column))?;
+                let segment = utf8_percent_encode(&value, SEGMENT).to_string();
+                Some((column.as_str().to_owned(), segment))
+            })
+            .collect::<Option<HashMap<_, _>>>()?;
+        let mut path = collection.to_owned();
+        ResourceDef::new(T::id_path(None))
+            .resource_path_from_map(&mut path, &segments)
+            .then_some(path)
     }
 
     async fn update(
+        request: HttpRequest,
         id: extractors::PrimaryKey<T>,
-        body: web::Json<T::Update>,
+        body: extractors::Body<T::Update>,
         db: web::Data<DatabaseConnection>,
     ) -> crate::Result<web::Json<T::Repr>> {
+        let ctx = Context {
+            request: &request,
+            db: &db,
+        };
+        permission::authorize::<T>(&ctx, Action::Update).await?;
+        let parent = nested::resolve::<T>(&ctx).await?;
+        let existing = Self::find(&ctx, &parent, id.clone())
+            .await?
+            .ok_or_else(NotFound)?;
+        permission::authorize_object::<T>(&ctx, Action::Update, &existing).await?;
+        traits::validate(&*body, &db).await?;
         let mut active_model = body.clone().into_active_model();
+        Self::set_parent_keys(&parent, &mut active_model);
         Self::set_primary_key(id.clone(), &mut active_model);
-        Ok(web::Json(
-            T::Entity::update(active_model).exec(&**db).await?.into(),
-        ))
+        T::before_update(&ctx, &mut active_model).await?;
+        let pending = Self::pending(&active_model, Some(&existing))?;
+        permission::authorize_object::<T>(&ctx, Action::Update, &pending).await?;
+        let model = T::perform_update(&ctx, active_model).await?;
+        T::after_update(&ctx, &model).await?;
+        Ok(web::Json(model.into()))
     }
 
     async fn replace(
         request: HttpRequest,
-        body: web::Json<T::Create>,
+        id: extractors::PrimaryKey<T>,
+        body: extractors::Body<T::Create>,
         db: web::Data<DatabaseConnection>,
-    ) -> crate::Result<web::Json<T::Repr>> {
+    ) -> crate::Result<HttpResponse> {
+        let ctx = Context {
+            request: &request,
+            db: &db,
+        };
+        permission::authorize::<T>(&ctx, Action::Replace).await?;
+        let parent = nested::resolve::<T>(&ctx).await?;
+        traits::validate(&*body, &db).await?;
+        match Self::find(&ctx, &parent, id.clone()).await? {
+            Some(existing) => {
+                permission::authorize_object::<T>(&ctx, Action::Replace, &existing).await?;
+            }
+            // A new row may be created, but a row outside of the base query or beneath
+            // another parent may not be taken over.
+            None => {
+                if T::Entity::find_by_id(id.clone())
+                    .one(&**db)
+                    .await?
+                    .is_some()
+                {
+                    return Err(NotFound());
+                }
+            }
+        }
         let mut active_model = body.clone().into_active_model();
-        let id = T::id_from_path(None, request.match_info())?;
-        Self::set_primary_key(id, &mut active_model);
-        Ok(web::Json(
-            T::Entity::insert(active_model)
-                .exec_with_returning(&**db)
-                .await?
-                .into(),
-        ))
+        Self::set_parent_keys(&parent, &mut active_model);
+        Self::set_primary_key(id.clone(), &mut active_model);
+        T::before_replace(&ctx, &mut active_model).await?;
+        // Replacing a row resets the columns that are not set, so it is checked as new.
+        let pending = Self::pending(&active_model, None)?;
+        permission::authorize_object::<T>(&ctx, Action::Replace, &pending).await?;
+        let (model, inserted) = T::perform_replace(&ctx, active_model).await?;
+        T::after_replace(&ctx, &model).await?;
+        let status = if inserted {
+            StatusCode::CREATED
+        } else {
+            StatusCode::OK
+        };
+        Ok(HttpResponse::build(status).json(T::Repr::from(model)))
     }
 
     async fn list(
+        request: HttpRequest,
         query: web::Query<T::Filter>,
+        shape: web::Query<Shape>,
         db: web::Data<DatabaseConnection>,
-    ) -> crate::Result<web::Json<PageNumberPagination<T::Repr>>> {
-        let page = query.page();
-        let limit = query.limit();
-        let pagination = T::Entity::find()
-            .filter(query.condition())
-            .paginate(&**db, limit);
-        let total = pagination.num_items().await?;
-        let items = pagination
-            .fetch_page(page)
-            .await?
-            .into_iter()
-            .map(From::from)
-            .collect();
-        Ok(web::Json(PageNumberPagination { total, items }))
+    ) -> crate::Result<web::Json<Json>> {
+        let ordering = ordering::resolve::<T>(query.ordering())?;
+        let ordered = ordering
+            .iter()
+            .map(|(column, _)| *column)
+            .collect::<Vec<_>>();
+        let ctx = Context {
+            request: &request,
+            db: &db,
+        };
+        permission::authorize::<T>(&ctx, Action::List).await?;
+        let parent = nested::resolve::<T>(&ctx).await?;
+        let select = shape.select::<T>(Self::select(&ctx, &parent), &ordered);
+        let select = select.filter(query.condition());
+        let page = T::Pagination::paginate(select, &ordering, &query, &request, &db).await?;
+        let mut body = serde_json::to_value(page)?;
+        if let Some(items) = body.get_mut("items").and_then(Json::as_array_mut) {
+            expand::expand::<T>(items, shape.expand.as_deref(), &ctx).await?;
+            items.iter_mut().for_each(|item| shape.prune(item));
+        }
+        Ok(web::Json(body))
     }
 }
+
+/// The characters that are escaped in a segment of a path.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// A route for `method`. Routes for `GET` also answer `HEAD`, whose response the server
+/// sends without a body.
+fn route(method: &Method) -> Route {
+    match *method {
+        Method::GET => web::route().guard(guard::Any(guard::Get()).or(guard::Head())),
+        _ => web::method(method.clone()),
+    }
+}
+
+/// Answers the methods of `resource` that it has no route for with 405, listing the
+/// `allowed` methods, and `HEAD` along with `GET`.
+fn allow(resource: Resource, allowed: Vec<Method>) -> Resource {
+    let allow = allowed
+        .iter()
+        .flat_map(|method| match *method {
+            Method::GET => vec![Method::GET, Method::HEAD],
+            _ => vec![method.clone()],
+        })
+        .map(|method| method.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    resource.default_service(web::to(move || {
+        let mut response = HttpResponse::from_error(MethodNotAllowed());
+        response.headers_mut().insert(
+            header::ALLOW,
+            HeaderValue::from_str(&allow).expect("methods are valid header values"),
+        );
+        ready(response)
+    }))
+}
+
+/// Matches the row of `E` with the given primary key.
+pub(crate) fn primary_key_condition<E: EntityTrait>(
+    primary_key: <E::PrimaryKey as PrimaryKeyTrait>::ValueType,
+) -> Condition {
+    let pk_columns = E::PrimaryKey::iter().map(PrimaryKeyToColumn::into_column);
+    let pk_values = primary_key.into_value_tuple();
+    pk_columns
+        .zip(pk_values)
+        .fold(Condition::all(), |condition, (column, value)| {
+            condition.add(column.eq(value))
+        })
+}
@@ -0,0 +1,112 @@
+use crate::{expand, value, Rest};
+use sea_orm::{
+    sea_query::SimpleExpr, ColumnTrait, EntityTrait, IdenStatic, Iterable, PrimaryKeyToColumn,
+    QuerySelect, Select,
+};
+use serde::Deserialize;
+use serde_json::Value as Json;
+use std::any::TypeId;
+
+type Column<T> = <<T as Rest>::Entity as EntityTrait>::Column;
+type Model<T> = <<T as Rest>::Entity as EntityTrait>::Model;
+
+/// The query parameters that shape each item of a response, rather than choosing which
+/// items it includes: `?expand=`, `?fields=` and `?omit=`.
+#[derive(Deserialize)]
+pub(crate) struct Shape {
+    pub expand: Option<String>,
+    fields: Option<String>,
+    omit: Option<String>,
+}
+
+fn split(names: &str) -> impl Iterator<Item = &str> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+impl Shape {
+    fn fields(&self) -> Option<&str> {
+        self.fields
+            .as_deref()
+            .filter(|fields| split(fields).next().is_some())
+    }
+
+    /// Whether the field `name` is included in the response. Fields named by `?omit=` are
+    /// left out, as are those not named by `?fields=`, if it is given.
+    fn includes(&self, name: &str) -> bool {
+        self.fields()
+            .is_none_or(|fields| split(fields).any(|field| field == name))
+            && !self
+                .omit
+                .as_deref()
+                .is_some_and(|omit| split(omit).any(|field| field == name))
+    }
+
+    /// Narrows `select` to the columns of `T` that the response includes.
+    ///
+    /// Each column that is left out is replaced by a placeholder of the same type, so the
+    /// model can still be read without loading the column. The placeholders reach
+    /// `Repr::from` in place of the values, so columns are only left out when the `Repr`
+    /// is the model itself, whose fields are the columns: any other `Repr` may build any
+    /// field from any column, and gets them all. The primary key, the columns in
+    /// `ordering` and the columns that requested expansions are found by are always loaded.
+    pub fn select<T: Rest + 'static>(
+        &self,
+        select: Select<T::Entity>,
+        ordering: &[Column<T>],
+    ) -> Select<T::Entity> {
+        if self.fields().is_none() && self.omit.is_none() {
+            return select;
+        }
+        if TypeId::of::<T::Repr>() != TypeId::of::<Model<T>>() {
+            return select;
+        }
+
+        let keys = expand::keys::<T>(self.expand.as_deref());
+        let required = |column: &Column<T>| {
+            <T::Entity as EntityTrait>::PrimaryKey::iter()
+                .any(|key| key.into_column().as_str() == column.as_str())
+                || ordering
+                    .iter()
+                    .any(|ordered| ordered.as_str() == column.as_str())
+                || keys.iter().any(|key| key == column.as_str())
+        };
+        let placeholders = Column::<T>::iter()
+            .map(|column| {
+                let placeholder = if self.includes(column.as_str()) || required(&column) {
+                    None
+                } else {
+                    value::placeholder(column.def().get_column_type())
+                };
+                (column, placeholder)
+            })
+            .collect::<Vec<_>>();
+        if placeholders
+            .iter()
+            .all(|(_, placeholder)| placeholder.is_none())
+        {
+            return select;
+        }
+
+        placeholders
+            .into_iter()
+            .fold(
+                select.select_only(),
+                |select, (column, placeholder)| match placeholder {
+                    Some(placeholder) => {
+                        select.column_as(SimpleExpr::Value(placeholder), column.as_str())
+                    }
+                    None => select.column(column),
+                },
+            )
+    }
+
+    /// Removes the fields of a serialized item that the response does not include.
+    pub fn prune(&self, item: &mut Json) {
+        if let Json::Object(item) = item {
+            item.retain(|name, _| self.includes(name));
+        }
+    }
+}
@@ -1,4 +1,8 @@
+use super::Validate;
 use sea_orm::{ActiveModelTrait, IntoActiveModel};
 use serde::de::DeserializeOwned;
 
-pub trait Create<A: ActiveModelTrait>: DeserializeOwned + Clone + IntoActiveModel<A> {}
+pub trait Create<A: ActiveModelTrait>:
+    DeserializeOwned + Clone + IntoActiveModel<A> + Validate
+{
+}
@@ -6,5 +6,11 @@ pub trait Filter: DeserializeOwned {
     fn offset(&self) -> usize;
     fn page(&self) -> usize;
     fn cursor(&self) -> Option<&str>;
+
+    /// The raw `?ordering=` parameter, if the filter accepts one.
+    fn ordering(&self) -> Option<&str> {
+        None
+    }
+
     fn condition(&self) -> Condition;
 }
@@ -0,0 +1,116 @@
+use crate::constraint::classify;
+use crate::upsert::upsert;
+use crate::{Context, Rest};
+use async_trait::async_trait;
+use sea_orm::{DeleteResult, EntityTrait, IntoActiveModel, Select};
+
+type Model<T> = <<T as Rest>::Entity as EntityTrait>::Model;
+
+/// Steps into the actions of a [`RestModel`](crate::RestModel).
+///
+/// Every action finds rows through [`Hooks::base_query`]. Each action that writes runs
+/// its `before_` hook on the active model about to be written, then its `perform_` hook
+/// to write it, then its `after_` hook on the result. An error from any of them fails the
+/// request. `#[derive(Rest)]` implements this trait with no extra steps,
+/// unless the type is marked `#[woof(hooks)]` to implement it by hand.
+#[async_trait(?Send)]
+pub trait Hooks: Rest
+where
+    Model<Self>: IntoActiveModel<Self::ActiveModel>,
+{
+    /// The rows that the resource exposes. Every action, including those on a single row,
+    /// only reaches the rows selected here, and responds with 404 for any other.
+    fn base_query(_ctx: &Context<'_>) -> Select<Self::Entity> {
+        Self::Entity::find()
+    }
+
+    async fn before_create(
+        _ctx: &Context<'_>,
+        _active_model: &mut Self::ActiveModel,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    async fn perform_create(
+        ctx: &Context<'_>,
+        active_model: Self::ActiveModel,
+    ) -> crate::Result<Model<Self>> {
+        Self::Entity::insert(active_model)
+            .exec_with_returning(ctx.db)
+            .await
+            .map_err(classify::<Self::Entity>)
+    }
+
+    async fn after_create(_ctx: &Context<'_>, _model: &Model<Self>) -> crate::Result<()> {
+        Ok(())
+    }
+
+    async fn before_update(
+        _ctx: &Context<'_>,
+        _active_model: &mut Self::ActiveModel,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    async fn perform_update(
+        ctx: &Context<'_>,
+        active_model: Self::ActiveModel,
+    ) -> crate::Result<Model<Self>> {
+        Self::Entity::update(active_model)
+            .exec(ctx.db)
+            .await
+            .map_err(classify::<Self::Entity>)
+    }
+
+    async fn after_update(_ctx: &Context<'_>, _model: &Model<Self>) -> crate::Result<()> {
+        Ok(())
+    }
+
+    async fn before_replace(
+        _ctx: &Context<'_>,
+        _active_model: &mut Self::ActiveModel,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// Inserts the row, or replaces the row with the same primary key, returning whether
+    /// it was inserted.
+    async fn perform_replace(
+        ctx: &Context<'_>,
+        active_model: Self::ActiveModel,
+    ) -> crate::Result<(Model<Self>, bool)> {
+        upsert::<Self>(active_model, ctx.db).await
+    }
+
+    async fn after_replace(_ctx: &Context<'_>, _model: &Model<Self>) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// Runs before deleting the row whose primary key is set on `active_model`.
+    async fn before_delete(
+        _ctx: &Context<'_>,
+        _active_model: &mut Self::ActiveModel,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// Deletes the row, which may be overridden to hide it instead.
+    async fn perform_delete(
+        ctx: &Context<'_>,
+        active_model: Self::ActiveModel,
+    ) -> crate::Result<DeleteResult> {
+        Self::Entity::delete(active_model)
+            .exec(ctx.db)
+            .await
+            .map_err(classify::<Self::Entity>)
+    }
+
+    /// Runs after the row has been deleted. It does not run when an idempotent delete
+    /// finds no row to delete.
+    async fn after_delete(
+        _ctx: &Context<'_>,
+        _active_model: &Self::ActiveModel,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+}
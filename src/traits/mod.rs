@@ -1,9 +1,14 @@
 mod create;
 mod filter;
+mod hooks;
 mod rest;
 mod update;
+mod validate;
 
 pub use create::Create;
 pub use filter::Filter;
-pub use rest::Rest;
+pub use hooks::Hooks;
+pub use rest::{id_from_path, Rest};
 pub use update::Update;
+pub(crate) use validate::validate;
+pub use validate::{Validate, ValidationErrors};
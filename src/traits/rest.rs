@@ -1,17 +1,25 @@
 use super::{Create, Filter, Update};
+use crate::error::{InvalidPathSegment, MissingPathSegment, UnsupportedPrimaryKey};
+use crate::expand::Expansion;
+use crate::pagination::{CountPolicy, Paginate};
+use crate::permission::{AllowAny, Permission};
+use crate::value;
 use actix_web::dev::{Path, Url};
 use sea_orm::{
-    ActiveModelTrait, EntityTrait, IdenStatic, Iterable, PrimaryKeyToColumn, PrimaryKeyTrait,
+    sea_query::{FromValueTuple, ValueTuple},
+    ActiveModelTrait, ColumnTrait, EntityTrait, IdenStatic, Iterable, Order, PrimaryKeyToColumn,
+    PrimaryKeyTrait,
 };
 use serde::Serialize;
 
-pub trait Rest {
+pub trait Rest: Sized {
     type Entity: EntityTrait;
     type Repr: Serialize + From<<Self::Entity as EntityTrait>::Model>;
     type ActiveModel: ActiveModelTrait<Entity = Self::Entity>;
     type Filter: Filter;
     type Create: Create<Self::ActiveModel>;
     type Update: Update<Self::ActiveModel>;
+    type Pagination: Paginate<Self>;
 
     fn id_from_path(
         scope: Option<&str>,
@@ -25,4 +33,89 @@ pub trait Rest {
             .collect::<Vec<_>>()
             .join("")
     }
+
+    /// The columns that clients may order list results by, using `?ordering=`.
+    fn orderable_columns() -> Vec<<Self::Entity as EntityTrait>::Column> {
+        vec![]
+    }
+
+    /// How list responses report the total number of matching rows.
+    fn count_policy() -> CountPolicy {
+        CountPolicy::Exact
+    }
+
+    /// Whether deleting a row that does not exist succeeds, rather than responding with 404.
+    fn idempotent_delete() -> bool {
+        false
+    }
+
+    /// The largest page size that clients may request, if any.
+    fn max_limit() -> Option<usize> {
+        None
+    }
+
+    /// The ordering of list results when the client does not request one. The primary
+    /// key is always used as a final tiebreaker, so this may be left empty.
+    fn default_ordering() -> Vec<(<Self::Entity as EntityTrait>::Column, Order)> {
+        vec![]
+    }
+
+    /// The related resources that clients may inline into responses, using `?expand=`.
+    fn expansions() -> Vec<Expansion<Self>> {
+        vec![]
+    }
+
+    /// How deeply expansions may be nested. `?expand=comments.author` has a depth of two.
+    fn max_expansion_depth() -> usize {
+        1
+    }
+
+    /// How many related rows a single expansion may find for a whole response. Requests
+    /// that would find more fail, and should ask for smaller pages.
+    fn max_expanded_rows() -> u64 {
+        1000
+    }
+
+    /// Who may perform each action. Every request is allowed by default.
+    fn permission() -> Box<dyn Permission<Self>> {
+        Box::new(AllowAny)
+    }
+}
+
+/// Reads the primary key of `E` from the path segments produced by [`Rest::id_path`],
+/// parsing each segment according to the type of its column.
+///
+/// This is what `#[derive(Rest)]` uses to implement [`Rest::id_from_path`], and works
+/// for composite primary keys of up to six columns. Longer keys fail every request.
+pub fn id_from_path<E>(
+    scope: Option<&str>,
+    path: &Path<Url>,
+) -> crate::Result<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>
+where
+    E: EntityTrait,
+{
+    let scope = scope.map(|scope| format!("{scope}_")).unwrap_or_default();
+    let mut values = E::PrimaryKey::iter()
+        .map(|key_col| {
+            let column = key_col.into_column();
+            let segment = scope.clone() + column.as_str();
+            let raw = path
+                .get(&segment)
+                .ok_or_else(|| MissingPathSegment(&segment))?;
+            value::parse(column.def().get_column_type(), raw)
+                .ok_or_else(|| InvalidPathSegment(&segment))
+        })
+        .collect::<crate::Result<Vec<_>>>()?
+        .into_iter();
+    let mut next = || values.next().unwrap();
+    let tuple = match E::PrimaryKey::iter().count() {
+        1 => ValueTuple::One(next()),
+        2 => ValueTuple::Two(next(), next()),
+        3 => ValueTuple::Three(next(), next(), next()),
+        4 => ValueTuple::Four(next(), next(), next(), next()),
+        5 => ValueTuple::Five(next(), next(), next(), next(), next()),
+        6 => ValueTuple::Six(next(), next(), next(), next(), next(), next()),
+        _ => return Err(UnsupportedPrimaryKey()),
+    };
+    Ok(FromValueTuple::from_value_tuple(tuple))
 }
@@ -0,0 +1,67 @@
+use crate::error::Problem;
+use actix_web::http::StatusCode;
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+use std::collections::BTreeMap;
+
+/// Checks a `Create` or `Update` payload before it is written.
+///
+/// Both checks run on every payload, and every problem they report is collected into a
+/// single 400 response keyed by field name. `#[derive(Create)]` and `#[derive(Update)]`
+/// implement this trait with no checks, unless the type is marked `#[woof(validate)]`.
+#[async_trait(?Send)]
+pub trait Validate {
+    /// Checks the payload on its own, such as the length of a string.
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+
+    /// Checks the payload against the database, such as whether a value is already taken.
+    async fn validate_with(
+        &self,
+        _db: &DatabaseConnection,
+        _errors: &mut ValidationErrors,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+/// The problems found with each field of a payload.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationErrors(BTreeMap<String, Vec<String>>);
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.entry(field.into()).or_default().push(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The problems found with `field`, if any.
+    pub fn field(&self, field: &str) -> &[String] {
+        self.0.get(field).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn problem(self) -> Problem {
+        let fields = self.0.keys().cloned().collect::<Vec<_>>().join(", ");
+        self.0.into_iter().fold(
+            Problem::new(StatusCode::BAD_REQUEST).with_detail(format!("Invalid fields: {fields}")),
+            |problem, (field, messages)| problem.with_error(field, messages),
+        )
+    }
+}
+
+/// Runs both checks of `payload`, failing with every problem they found.
+pub(crate) async fn validate<V: Validate>(
+    payload: &V,
+    db: &DatabaseConnection,
+) -> crate::Result<()> {
+    let mut errors = ValidationErrors::default();
+    payload.validate(&mut errors);
+    payload.validate_with(db, &mut errors).await?;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.problem().into())
+    }
+}
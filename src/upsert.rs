@@ -0,0 +1,76 @@
+use crate::constraint::classify;
+use crate::Rest;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult,
+    IdenStatic, IntoActiveModel, Iterable, PrimaryKeyToColumn, QueryTrait, Statement,
+};
+
+type Model<T> = <<T as Rest>::Entity as EntityTrait>::Model;
+
+/// The name of the extra column returned by an upsert, which is true if the row was
+/// inserted rather than updated.
+const INSERTED: &str = "woof_inserted";
+
+/// Inserts `active_model`, or replaces the row with the same primary key if there is one,
+/// returning the row and whether it was inserted.
+///
+/// Replacing a row resets every column that is not set by `active_model` to its default,
+/// so the result is the same as if the row had been inserted. sea-query cannot yet build
+/// `ON CONFLICT` clauses, so this is written out by hand, and only supported on Postgres.
+/// Other databases fail with [`UnsupportedReplace`](crate::error::UnsupportedReplace)
+/// rather than inserting a row that may already exist.
+pub(crate) async fn upsert<T>(
+    active_model: T::ActiveModel,
+    db: &DatabaseConnection,
+) -> crate::Result<(Model<T>, bool)>
+where
+    T: Rest,
+    Model<T>: IntoActiveModel<T::ActiveModel>,
+{
+    let backend = db.get_database_backend();
+    if backend != DbBackend::Postgres {
+        return Err(crate::error::UnsupportedReplace(backend));
+    }
+
+    let primary_key = <T::Entity as EntityTrait>::PrimaryKey::iter()
+        .map(|key| quote(key.into_column().as_str()))
+        .collect::<Vec<_>>();
+    let mut assignments = <T::Entity as EntityTrait>::Column::iter()
+        .filter(|column| !primary_key.contains(&quote(column.as_str())))
+        .map(|column| {
+            let name = quote(column.as_str());
+            if active_model.is_not_set(column) {
+                format!("{name} = DEFAULT")
+            } else {
+                format!("{name} = EXCLUDED.{name}")
+            }
+        })
+        .collect::<Vec<_>>();
+    if assignments.is_empty() {
+        // `DO NOTHING` would not return the existing row, so update it to itself instead.
+        assignments.push(format!("{0} = EXCLUDED.{0}", primary_key[0]));
+    }
+
+    let insert = T::Entity::insert(active_model).build(backend);
+    let statement = Statement {
+        sql: format!(
+            "{} ON CONFLICT ({}) DO UPDATE SET {} RETURNING *, (xmax = 0) AS {}",
+            insert.sql,
+            primary_key.join(", "),
+            assignments.join(", "),
+            quote(INSERTED),
+        ),
+        ..insert
+    };
+    let row = db
+        .query_one(statement)
+        .await
+        .map_err(classify::<T::Entity>)?
+        .ok_or_else(|| sea_orm::DbErr::RecordNotFound("Upserted row was not returned".into()))?;
+    let inserted = row.try_get("", INSERTED)?;
+    Ok((Model::<T>::from_query_result(&row, "")?, inserted))
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
@@ -0,0 +1,100 @@
+use sea_orm::prelude::{
+    ColumnType, Date, DateTime, DateTimeWithTimeZone, Decimal, Time, Uuid, Value,
+};
+
+/// Parses a raw string (from a path segment or query string) into a `Value` of
+/// the variant sea-orm expects for a column of the given type.
+///
+/// Returns `None` if the string is not a valid value for the column, or if the
+/// column type has no textual representation (e.g. binary).
+pub(crate) fn parse(column_type: &ColumnType, raw: &str) -> Option<Value> {
+    let value = match column_type {
+        ColumnType::Char(_)
+        | ColumnType::String(_)
+        | ColumnType::Text
+        | ColumnType::Custom(_)
+        | ColumnType::Enum(..) => raw.to_owned().into(),
+        ColumnType::TinyInteger => raw.parse::<i8>().ok()?.into(),
+        ColumnType::SmallInteger => raw.parse::<i16>().ok()?.into(),
+        ColumnType::Integer => raw.parse::<i32>().ok()?.into(),
+        ColumnType::BigInteger => raw.parse::<i64>().ok()?.into(),
+        ColumnType::TinyUnsigned => raw.parse::<u8>().ok()?.into(),
+        ColumnType::SmallUnsigned => raw.parse::<u16>().ok()?.into(),
+        ColumnType::Unsigned => raw.parse::<u32>().ok()?.into(),
+        ColumnType::BigUnsigned => raw.parse::<u64>().ok()?.into(),
+        ColumnType::Float => raw.parse::<f32>().ok()?.into(),
+        ColumnType::Double => raw.parse::<f64>().ok()?.into(),
+        ColumnType::Decimal(_) | ColumnType::Money(_) => raw.parse::<Decimal>().ok()?.into(),
+        ColumnType::DateTime | ColumnType::Timestamp => raw.parse::<DateTime>().ok()?.into(),
+        ColumnType::TimestampWithTimeZone => {
+            DateTimeWithTimeZone::parse_from_rfc3339(raw).ok()?.into()
+        }
+        ColumnType::Time => raw.parse::<Time>().ok()?.into(),
+        ColumnType::Date => raw.parse::<Date>().ok()?.into(),
+        ColumnType::Boolean => raw.parse::<bool>().ok()?.into(),
+        ColumnType::Uuid => Uuid::parse_str(raw).ok()?.into(),
+        ColumnType::Binary | ColumnType::Json | ColumnType::JsonBinary => return None,
+    };
+    Some(value)
+}
+
+/// Formats a `Value` as a string which [`parse`] reads back into an equal value, given
+/// the type of the column it came from.
+///
+/// Returns `None` for `NULL` and for values that have no textual representation.
+pub(crate) fn format(value: &Value) -> Option<String> {
+    let formatted = match value {
+        Value::Bool(value) => value.as_ref()?.to_string(),
+        Value::TinyInt(value) => value.as_ref()?.to_string(),
+        Value::SmallInt(value) => value.as_ref()?.to_string(),
+        Value::Int(value) => value.as_ref()?.to_string(),
+        Value::BigInt(value) => value.as_ref()?.to_string(),
+        Value::TinyUnsigned(value) => value.as_ref()?.to_string(),
+        Value::SmallUnsigned(value) => value.as_ref()?.to_string(),
+        Value::Unsigned(value) => value.as_ref()?.to_string(),
+        Value::BigUnsigned(value) => value.as_ref()?.to_string(),
+        Value::Float(value) => value.as_ref()?.to_string(),
+        Value::Double(value) => value.as_ref()?.to_string(),
+        Value::String(value) => value.as_deref()?.clone(),
+        Value::Decimal(value) => value.as_ref()?.to_string(),
+        Value::Uuid(value) => value.as_ref()?.to_string(),
+        Value::ChronoDate(value) => value.as_ref()?.to_string(),
+        Value::ChronoTime(value) => value.as_ref()?.to_string(),
+        Value::ChronoDateTime(value) => value.as_ref()?.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        Value::ChronoDateTimeUtc(value) => value.as_ref()?.to_rfc3339(),
+        Value::ChronoDateTimeLocal(value) => value.as_ref()?.to_rfc3339(),
+        Value::ChronoDateTimeWithTimeZone(value) => value.as_ref()?.to_rfc3339(),
+        _ => return None,
+    };
+    Some(formatted)
+}
+
+/// A cheap stand-in for a value of a column that a response leaves out, which is selected
+/// in place of the column so that the model can still be read without loading it.
+///
+/// Returns `None` for column types that have no suitable stand-in.
+pub(crate) fn placeholder(column_type: &ColumnType) -> Option<Value> {
+    let raw = match column_type {
+        ColumnType::Char(_) | ColumnType::String(_) | ColumnType::Text => "",
+        ColumnType::TinyInteger
+        | ColumnType::SmallInteger
+        | ColumnType::Integer
+        | ColumnType::BigInteger
+        | ColumnType::TinyUnsigned
+        | ColumnType::SmallUnsigned
+        | ColumnType::Unsigned
+        | ColumnType::BigUnsigned
+        | ColumnType::Float
+        | ColumnType::Double
+        | ColumnType::Decimal(_)
+        | ColumnType::Money(_) => "0",
+        ColumnType::DateTime | ColumnType::Timestamp => "1970-01-01T00:00:00",
+        ColumnType::TimestampWithTimeZone => "1970-01-01T00:00:00Z",
+        ColumnType::Time => "00:00:00",
+        ColumnType::Date => "1970-01-01",
+        ColumnType::Boolean => "false",
+        ColumnType::Uuid => "00000000-0000-0000-0000-000000000000",
+        _ => return None,
+    };
+    parse(column_type, raw)
+}
@@ -0,0 +1,602 @@
+use actix_web::{http::header, http::StatusCode, test, web, App, HttpResponse};
+use ring::{hmac, rand, signature};
+use sea_orm::{DatabaseConnection, DbBackend, MockDatabase, MockExecResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use woof::auth::{ApiKeyAuth, BasicAuth, JwtAuth};
+use woof::{Authentication, CurrentUser, Hooks, RestModel};
+
+mod users {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "users")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub username: String,
+        pub password: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+mod api_keys {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "api_keys")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub key: String,
+        pub user_id: i32,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::users::Entity",
+            from = "Column::UserId",
+            to = "super::users::Column::Id"
+        )]
+        User,
+    }
+
+    impl Related<super::users::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::User.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+mod notes {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{
+        Action, And, Context, Create, IsAuthenticated, IsOwner, Not, Or, Permission, ReadOnly,
+        Rest, Update,
+    };
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "notes")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub owner: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub owner: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub owner: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        permission = "Or(ReadOnly, And(IsAuthenticated, IsOwner(Column::Owner)))"
+    )]
+    pub struct RestModel;
+
+    /// Allows requests marked as made by staff, whatever the row.
+    pub struct FromStaff;
+
+    #[async_trait::async_trait(?Send)]
+    impl<T: Rest> Permission<T> for FromStaff {
+        async fn has_permission(&self, ctx: &Context<'_>, _action: Action) -> woof::Result<bool> {
+            Ok(ctx.request.headers().contains_key("x-staff"))
+        }
+    }
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        permission = "Or(FromStaff, IsOwner(Column::Owner))"
+    )]
+    pub struct StaffOrOwnerRestModel;
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        permission = "And(IsOwner(Column::Owner), Not(FromStaff))"
+    )]
+    pub struct OwnerNotStaffRestModel;
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+const SECRET: &[u8] = b"secret";
+
+/// A token of the `claims`, whose signature `sign` makes with the `alg` algorithm.
+fn jws(alg: &str, claims: serde_json::Value, sign: impl FnOnce(&[u8]) -> Vec<u8>) -> String {
+    let encode =
+        |json: serde_json::Value| base64::encode_config(json.to_string(), base64::URL_SAFE_NO_PAD);
+    let message = format!(
+        "{}.{}",
+        encode(json!({ "alg": alg, "typ": "JWT" })),
+        encode(claims)
+    );
+    let signature = sign(message.as_bytes());
+    format!(
+        "{message}.{}",
+        base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+    )
+}
+
+fn token(claims: serde_json::Value) -> String {
+    jws("HS256", claims, |message| {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, SECRET);
+        hmac::sign(&key, message).as_ref().to_vec()
+    })
+}
+
+fn rs256_token(claims: serde_json::Value) -> String {
+    jws("RS256", claims, |message| {
+        let key = signature::RsaKeyPair::from_pkcs8(include_bytes!("keys/rsa.pk8")).unwrap();
+        let mut signature = vec![0; key.public_modulus_len()];
+        key.sign(
+            &signature::RSA_PKCS1_SHA256,
+            &rand::SystemRandom::new(),
+            message,
+            &mut signature,
+        )
+        .unwrap();
+        signature
+    })
+}
+
+async fn whoami(user: CurrentUser<Claims>) -> HttpResponse {
+    HttpResponse::Ok().body(user.sub.clone())
+}
+
+/// Calls an app that has no database, which tokens are checked without.
+async fn call_jwt(
+    backend: JwtAuth<Claims>,
+    authorization: Option<String>,
+) -> (StatusCode, Option<String>, String) {
+    let app = test::init_service(
+        App::new()
+            .wrap(Authentication::new(backend))
+            .route("/me", web::get().to(whoami)),
+    )
+    .await;
+    let mut request = test::TestRequest::get().uri("/me");
+    if let Some(authorization) = authorization {
+        request = request.insert_header((header::AUTHORIZATION, authorization));
+    }
+    let response = test::call_service(&app, request.to_request()).await;
+    let status = response.status();
+    let challenge = response
+        .headers()
+        .get(header::WWW_AUTHENTICATE)
+        .map(|value| value.to_str().unwrap().to_owned());
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    (status, challenge, body)
+}
+
+#[actix_web::test]
+async fn jwt_authenticates_user() {
+    let token = token(json!({ "sub": "alice", "exp": u64::MAX / 2 }));
+    let (status, _, body) = call_jwt(JwtAuth::hs256(SECRET), Some(format!("Bearer {token}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "alice");
+}
+
+#[actix_web::test]
+async fn jwt_rejects_expired_token() {
+    let token = token(json!({ "sub": "alice", "exp": 1 }));
+    let (status, challenge, _) =
+        call_jwt(JwtAuth::hs256(SECRET), Some(format!("Bearer {token}"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(challenge.as_deref(), Some("Bearer"));
+}
+
+#[actix_web::test]
+async fn jwt_rejects_forged_token() {
+    let token = token(json!({ "sub": "alice", "exp": u64::MAX / 2 }));
+    let (header, _) = token.rsplit_once('.').unwrap();
+    let forged = format!(
+        "{header}.{}",
+        base64::encode_config("nope", base64::URL_SAFE_NO_PAD)
+    );
+    let (status, _, _) = call_jwt(JwtAuth::hs256(SECRET), Some(format!("Bearer {forged}"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn jwt_rejects_negative_fractional_expiry() {
+    let token = token(json!({ "sub": "alice", "exp": -0.5 }));
+    let (status, _, _) = call_jwt(JwtAuth::hs256(SECRET), Some(format!("Bearer {token}"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn jwt_rejects_non_numeric_expiry() {
+    let token = token(json!({ "sub": "alice", "exp": "never" }));
+    let (status, _, _) = call_jwt(JwtAuth::hs256(SECRET), Some(format!("Bearer {token}"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn jwt_rejects_future_not_before() {
+    let token = token(json!({ "sub": "alice", "nbf": 1e12 }));
+    let (status, _, _) = call_jwt(JwtAuth::hs256(SECRET), Some(format!("Bearer {token}"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn rs256_authenticates_user() {
+    let token = rs256_token(json!({ "sub": "alice", "exp": u64::MAX / 2 }));
+    let backend = JwtAuth::rs256(include_bytes!("keys/rsa.pub.der"));
+    let (status, _, body) = call_jwt(backend, Some(format!("Bearer {token}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "alice");
+}
+
+#[actix_web::test]
+async fn rs256_rejects_hs256_token() {
+    let token = token(json!({ "sub": "alice", "exp": u64::MAX / 2 }));
+    let backend = JwtAuth::rs256(include_bytes!("keys/rsa.pub.der"));
+    let (status, _, _) = call_jwt(backend, Some(format!("Bearer {token}"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn anonymous_request_is_challenged() {
+    let (status, challenge, _) = call_jwt(JwtAuth::hs256(SECRET), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(challenge.as_deref(), Some("Bearer"));
+}
+
+/// Calls `/me` with the API key `key`, returning the status and the statements run.
+async fn call_api_key(
+    backend: ApiKeyAuth<api_keys::Entity, users::Entity>,
+    db: DatabaseConnection,
+    key: &str,
+) -> (StatusCode, Vec<sea_orm::Transaction>) {
+    let db = web::Data::new(db);
+    let status = {
+        let app = test::init_service(
+            App::new()
+                .wrap(Authentication::new(backend))
+                .app_data(db.clone())
+                .route(
+                    "/me",
+                    web::get().to(|user: CurrentUser<users::Model>| async move {
+                        HttpResponse::Ok().body(user.username.clone())
+                    }),
+                ),
+        )
+        .await;
+        let request = test::TestRequest::get()
+            .uri("/me")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {key}")))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        response.status()
+    };
+    let db = Arc::try_unwrap(db.into_inner()).ok().unwrap();
+    (status, db.into_transaction_log())
+}
+
+/// A database that finds the API key with `key`, and then alice.
+fn alices_key(key: &str) -> DatabaseConnection {
+    MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![api_keys::Model {
+            id: 1,
+            key: key.to_owned(),
+            user_id: 1,
+        }]])
+        .append_query_results(vec![vec![users::Model {
+            id: 1,
+            username: "alice".to_owned(),
+            password: "hunter2".to_owned(),
+        }]])
+        .into_connection()
+}
+
+#[actix_web::test]
+async fn api_key_authenticates_user() {
+    let backend = ApiKeyAuth::new(api_keys::Column::Key);
+    let (status, log) = call_api_key(backend, alices_key("key-123"), "key-123").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(format!("{:?}", log[0]).contains("\"key-123\""));
+}
+
+#[actix_web::test]
+async fn api_key_rejects_unknown_key() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![Vec::<api_keys::Model>::new()])
+        .into_connection();
+    let backend = ApiKeyAuth::new(api_keys::Column::Key);
+    let (status, _) = call_api_key(backend, db, "key-456").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+/// The SHA-256 digest of `key-123`.
+const KEY_DIGEST: &str = "65803be0872fa538d3ac513edadd6699f54dd8b4a566ee1cf6b185c8cc803949";
+
+#[actix_web::test]
+async fn hashed_api_key_authenticates_user() {
+    let backend = ApiKeyAuth::new(api_keys::Column::Key).hashed();
+    let (status, log) = call_api_key(backend, alices_key(KEY_DIGEST), "key-123").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(format!("{:?}", log[0]).contains(KEY_DIGEST));
+}
+
+#[actix_web::test]
+async fn hashed_api_key_rejects_unknown_key() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![Vec::<api_keys::Model>::new()])
+        .into_connection();
+    let backend = ApiKeyAuth::new(api_keys::Column::Key).hashed();
+    let (status, log) = call_api_key(backend, db, "key-456").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(!format!("{:?}", log[0]).contains("key-456"));
+}
+
+#[actix_web::test]
+async fn api_key_requires_database() {
+    let backend = ApiKeyAuth::<api_keys::Entity, users::Entity>::new(api_keys::Column::Key);
+    let app = test::init_service(
+        App::new()
+            .wrap(Authentication::new(backend))
+            .route("/", web::get().to(HttpResponse::Ok)),
+    )
+    .await;
+    let anonymous = test::TestRequest::get().uri("/").to_request();
+    assert_eq!(
+        test::call_service(&app, anonymous).await.status(),
+        StatusCode::OK
+    );
+    let request = test::TestRequest::get()
+        .uri("/")
+        .insert_header((header::AUTHORIZATION, "Bearer key-123"))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+async fn call_basic(db: DatabaseConnection, password: &str) -> StatusCode {
+    let backend = BasicAuth::<users::Entity>::new(users::Column::Username, |user, password| {
+        user.password == password
+    });
+    let app = test::init_service(
+        App::new()
+            .wrap(Authentication::new(backend))
+            .app_data(web::Data::new(db))
+            .route(
+                "/me",
+                web::get().to(|user: CurrentUser<users::Model>| async move {
+                    HttpResponse::Ok().body(user.username.clone())
+                }),
+            ),
+    )
+    .await;
+    let credentials = base64::encode(format!("alice:{password}"));
+    let request = test::TestRequest::get()
+        .uri("/me")
+        .insert_header((header::AUTHORIZATION, format!("Basic {credentials}")))
+        .to_request();
+    test::call_service(&app, request).await.status()
+}
+
+fn alice() -> DatabaseConnection {
+    MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![users::Model {
+            id: 1,
+            username: "alice".to_owned(),
+            password: "hunter2".to_owned(),
+        }]])
+        .into_connection()
+}
+
+#[actix_web::test]
+async fn basic_authenticates_user() {
+    assert_eq!(call_basic(alice(), "hunter2").await, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn basic_rejects_wrong_password() {
+    assert_eq!(
+        call_basic(alice(), "hunter3").await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+fn note(owner: &str) -> notes::Model {
+    notes::Model {
+        id: 1,
+        owner: owner.to_owned(),
+    }
+}
+
+/// A database whose first query finds a note owned by alice, and whose first statement
+/// deletes it.
+fn alices_note() -> DatabaseConnection {
+    MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![note("alice")]])
+        .append_exec_results(vec![MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }])
+        .into_connection()
+}
+
+async fn call_notes(
+    db: DatabaseConnection,
+    request: test::TestRequest,
+    user: Option<&str>,
+) -> StatusCode {
+    call_notes_of::<notes::RestModel>(db, request, user).await
+}
+
+/// Calls the notes resource `T`, as `user` if given.
+async fn call_notes_of<T>(
+    db: DatabaseConnection,
+    request: test::TestRequest,
+    user: Option<&str>,
+) -> StatusCode
+where
+    T: Hooks<Entity = notes::Entity, ActiveModel = notes::ActiveModel> + 'static,
+{
+    let app = test::init_service(
+        App::new()
+            .wrap(Authentication::new(JwtAuth::<Claims>::hs256(SECRET)))
+            .app_data(web::Data::new(db))
+            .service(RestModel::<T>::new("/notes").as_service()),
+    )
+    .await;
+    let mut request = request;
+    if let Some(user) = user {
+        let token = token(json!({ "sub": user, "exp": u64::MAX / 2 }));
+        request = request.insert_header((header::AUTHORIZATION, format!("Bearer {token}")));
+    }
+    test::call_service(&app, request.to_request())
+        .await
+        .status()
+}
+
+#[actix_web::test]
+async fn anonymous_may_read() {
+    let request = test::TestRequest::get().uri("/notes/1");
+    assert_eq!(
+        call_notes(alices_note(), request, None).await,
+        StatusCode::OK
+    );
+}
+
+#[actix_web::test]
+async fn anonymous_may_not_delete() {
+    let request = test::TestRequest::delete().uri("/notes/1");
+    assert_eq!(
+        call_notes(alices_note(), request, None).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_web::test]
+async fn owner_may_delete() {
+    let request = test::TestRequest::delete().uri("/notes/1");
+    assert_eq!(
+        call_notes(alices_note(), request, Some("alice")).await,
+        StatusCode::NO_CONTENT
+    );
+}
+
+#[actix_web::test]
+async fn others_may_not_delete() {
+    let request = test::TestRequest::delete().uri("/notes/1");
+    assert_eq!(
+        call_notes(alices_note(), request, Some("bob")).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[actix_web::test]
+async fn owner_may_create() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![note("bob")]])
+        .into_connection();
+    let request = test::TestRequest::post()
+        .uri("/notes")
+        .set_json(json!({ "owner": "bob" }));
+    assert_eq!(
+        call_notes(db, request, Some("bob")).await,
+        StatusCode::CREATED
+    );
+}
+
+#[actix_web::test]
+async fn others_may_not_create() {
+    let db = MockDatabase::new(DbBackend::Postgres).into_connection();
+    let request = test::TestRequest::post()
+        .uri("/notes")
+        .set_json(json!({ "owner": "alice" }));
+    assert_eq!(
+        call_notes(db, request, Some("bob")).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[actix_web::test]
+async fn others_may_not_replace_missing_row() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![Vec::<notes::Model>::new(), vec![]])
+        .into_connection();
+    let request = test::TestRequest::put()
+        .uri("/notes/2")
+        .set_json(json!({ "owner": "alice" }));
+    assert_eq!(
+        call_notes(db, request, Some("bob")).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[actix_web::test]
+async fn owner_may_not_give_row_away() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![note("bob")]])
+        .into_connection();
+    let request = test::TestRequest::patch()
+        .uri("/notes/1")
+        .set_json(json!({ "owner": "alice" }));
+    assert_eq!(
+        call_notes(db, request, Some("bob")).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[actix_web::test]
+async fn either_permission_allows_row() {
+    let delete = || test::TestRequest::delete().uri("/notes/1");
+    let staff = || delete().insert_header(("x-staff", "1"));
+    for (request, user, expected) in [
+        (delete(), "alice", StatusCode::NO_CONTENT),
+        (staff(), "bob", StatusCode::NO_CONTENT),
+        // Bob is not staff, so the row is not let through by the staff permission.
+        (delete(), "bob", StatusCode::FORBIDDEN),
+    ] {
+        let status =
+            call_notes_of::<notes::StaffOrOwnerRestModel>(alices_note(), request, Some(user)).await;
+        assert_eq!(status, expected, "{user}");
+    }
+}
+
+#[actix_web::test]
+async fn negated_permission_allows_every_row() {
+    let delete = || test::TestRequest::delete().uri("/notes/1");
+    let staff = || delete().insert_header(("x-staff", "1"));
+    for (request, user, expected) in [
+        (delete(), "alice", StatusCode::NO_CONTENT),
+        (staff(), "alice", StatusCode::FORBIDDEN),
+        (delete(), "bob", StatusCode::FORBIDDEN),
+    ] {
+        let status =
+            call_notes_of::<notes::OwnerNotStaffRestModel>(alices_note(), request, Some(user))
+                .await;
+        assert_eq!(status, expected, "{user}");
+    }
+}
@@ -0,0 +1,46 @@
+// Each test crate uses its own share of these helpers.
+#![allow(dead_code)]
+
+use actix_web::dev::HttpServiceFactory;
+use actix_web::http::{header::HeaderMap, StatusCode};
+use actix_web::{test, web, App};
+use sea_orm::{MockDatabase, Transaction};
+use std::sync::Arc;
+
+/// Sends `request` to an app serving `service` over `db`, returning the status, the
+/// body (`null` when empty, a string when not JSON) and the statements the service ran.
+pub async fn call(
+    service: impl HttpServiceFactory + 'static,
+    db: MockDatabase,
+    request: test::TestRequest,
+) -> (StatusCode, serde_json::Value, Vec<Transaction>) {
+    let (status, _, body, log) = call_with_headers(service, db, request).await;
+    (status, body, log)
+}
+
+/// Like [`call`], also returning the headers of the response.
+pub async fn call_with_headers(
+    service: impl HttpServiceFactory + 'static,
+    db: MockDatabase,
+    request: test::TestRequest,
+) -> (StatusCode, HeaderMap, serde_json::Value, Vec<Transaction>) {
+    let db = web::Data::new(db.into_connection());
+    let (status, headers, body) = {
+        let app = test::init_service(App::new().app_data(db.clone()).service(service)).await;
+        let response = test::call_service(&app, request.to_request()).await;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = test::read_body(response).await;
+        let body = match body.is_empty() {
+            true => serde_json::Value::Null,
+            false => serde_json::from_slice(&body)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&body).into()),
+        };
+        (status, headers, body)
+    };
+    let log = Arc::try_unwrap(db.into_inner())
+        .ok()
+        .unwrap()
+        .into_transaction_log();
+    (status, headers, body, log)
+}
@@ -0,0 +1,137 @@
+use actix_web::http::StatusCode;
+use actix_web::{test, web};
+use sea_orm::{DbBackend, MockDatabase, Transaction};
+use serde_json::json;
+use woof::{CursorKey, RestModel};
+
+mod common;
+
+mod posts {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "posts")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub title: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub title: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub title: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        pagination = "woof::CursorPagination<Model>",
+        orderable = "title"
+    )]
+    pub struct RestModel;
+}
+
+/// Lists the posts at `uri` a page at a time by cursor, returning the response and the
+/// query that found the page.
+async fn list(
+    rows: Vec<posts::Model>,
+    uri: &str,
+) -> (StatusCode, serde_json::Value, Option<Transaction>) {
+    let service = web::scope("")
+        .app_data(web::Data::new(CursorKey::new(b"secret")))
+        .service(RestModel::<posts::RestModel>::new("/posts").as_service());
+    let db = MockDatabase::new(DbBackend::Postgres).append_query_results(vec![rows]);
+    let (status, body, log) = common::call(service, db, test::TestRequest::get().uri(uri)).await;
+    (status, body, log.into_iter().next())
+}
+
+fn post(id: i32, title: &str) -> posts::Model {
+    posts::Model {
+        id,
+        title: title.to_owned(),
+    }
+}
+
+#[actix_web::test]
+async fn cursor_pages_from_row() {
+    let rows = vec![post(2, "B"), post(1, "A")];
+    let (status, first, _) = list(rows, "/posts?ordering=-title&limit=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["items"], json!([{ "id": 2, "title": "B" }]));
+    assert_eq!(first["previous"], json!(null));
+
+    let next = first["next"].as_str().unwrap();
+    let uri = format!("/posts?ordering=-title&limit=1&cursor={next}");
+    let (status, second, query) = list(vec![post(1, "A")], &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(second["items"], json!([{ "id": 1, "title": "A" }]));
+    assert_eq!(second["next"], json!(null));
+    assert_eq!(
+        query,
+        Some(Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            concat!(
+                r#"SELECT "posts"."id", "posts"."title" FROM "posts" "#,
+                r#"WHERE "posts"."title" < $1 OR ("posts"."title" = $2 AND "posts"."id" > $3) "#,
+                r#"ORDER BY "posts"."title" DESC, "posts"."id" ASC LIMIT $4"#,
+            ),
+            vec!["B".into(), "B".into(), 2i32.into(), 2u64.into()]
+        ))
+    );
+
+    // Back to the first page, walking backwards from the row it ended on.
+    let previous = second["previous"].as_str().unwrap();
+    let uri = format!("/posts?ordering=-title&limit=1&cursor={previous}");
+    let (status, _, query) = list(vec![post(2, "B")], &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        query,
+        Some(Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            concat!(
+                r#"SELECT "posts"."id", "posts"."title" FROM "posts" "#,
+                r#"WHERE "posts"."title" > $1 OR ("posts"."title" = $2 AND "posts"."id" < $3) "#,
+                r#"ORDER BY "posts"."title" ASC, "posts"."id" DESC LIMIT $4"#,
+            ),
+            vec!["A".into(), "A".into(), 1i32.into(), 2u64.into()]
+        ))
+    );
+}
+
+#[actix_web::test]
+async fn cursor_for_another_ordering() {
+    let rows = vec![post(2, "B"), post(1, "A")];
+    let (_, first, _) = list(rows, "/posts?ordering=-title&limit=1").await;
+    let next = first["next"].as_str().unwrap();
+    let uri = format!("/posts?ordering=title&limit=1&cursor={next}");
+    let (status, _, query) = list(vec![], &uri).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(query, None);
+}
+
+#[actix_web::test]
+async fn cursor_tampered_with() {
+    let rows = vec![post(2, "B"), post(1, "A")];
+    let (_, first, _) = list(rows, "/posts?ordering=-title&limit=1").await;
+    let (_, tag) = first["next"].as_str().unwrap().split_once('.').unwrap();
+    let payload = base64::encode_config(
+        r#"{"d":"n","o":"-title,id","v":["Z","0"]}"#,
+        base64::URL_SAFE_NO_PAD,
+    );
+    let uri = format!("/posts?ordering=-title&limit=1&cursor={payload}.{tag}");
+    let (status, _, query) = list(vec![], &uri).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(query, None);
+}
@@ -0,0 +1,205 @@
+use actix_web::http::StatusCode;
+use actix_web::test;
+use sea_orm::{DbBackend, MockDatabase, Transaction, Value};
+use serde_json::json;
+use std::collections::BTreeMap;
+use woof::{Hooks, RestModel};
+
+mod common;
+
+mod profiles {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "profiles")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub name: String,
+        pub bio: Option<String>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub name: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub name: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>")]
+    pub struct RestModel;
+
+    /// A profile whose `about` is made from its bio.
+    #[derive(Serialize)]
+    pub struct Summary {
+        pub name: String,
+        pub about: String,
+    }
+
+    impl From<Model> for Summary {
+        fn from(model: Model) -> Self {
+            Self {
+                name: model.name,
+                about: model.bio.unwrap_or_default(),
+            }
+        }
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>", repr = "Summary")]
+    pub struct SummaryRestModel;
+}
+
+fn profile() -> profiles::Model {
+    profiles::Model {
+        id: 1,
+        name: "Ann".to_owned(),
+        bio: Some("Hello".to_owned()),
+    }
+}
+
+/// Gets `uri` from the profiles resource, returning the response and the query that
+/// found the profiles.
+async fn get<T>(db: MockDatabase, uri: &str) -> (StatusCode, serde_json::Value, Option<Transaction>)
+where
+    T: Hooks<Entity = profiles::Entity, ActiveModel = profiles::ActiveModel> + 'static,
+{
+    let resource = RestModel::<T>::new("/profiles");
+    let request = test::TestRequest::get().uri(uri);
+    let (status, body, mut log) = common::call(resource.as_service(), db, request).await;
+    (status, body, log.pop())
+}
+
+async fn get_profile(uri: &str) -> (StatusCode, serde_json::Value) {
+    let db = MockDatabase::new(DbBackend::Postgres).append_query_results(vec![vec![profile()]]);
+    let (status, body, _) = get::<profiles::RestModel>(db, uri).await;
+    (status, body)
+}
+
+/// Lists the profiles at `uri`, returning the items and the query that found them.
+async fn list_profiles(uri: &str) -> (serde_json::Value, Option<Transaction>) {
+    list::<profiles::RestModel>(uri).await
+}
+
+async fn list<T>(uri: &str) -> (serde_json::Value, Option<Transaction>)
+where
+    T: Hooks<Entity = profiles::Entity, ActiveModel = profiles::ActiveModel> + 'static,
+{
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![BTreeMap::from([(
+            "num_items",
+            Value::from(1i64),
+        )])]])
+        .append_query_results(vec![vec![profile()]]);
+    let (status, body, query) = get::<T>(db, uri).await;
+    assert_eq!(status, StatusCode::OK);
+    (body["items"].clone(), query)
+}
+
+fn list_profiles_sql(columns: &str, values: Vec<Value>) -> Option<Transaction> {
+    let n = values.len();
+    Some(Transaction::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            r#"SELECT {columns} FROM "profiles" ORDER BY "profiles"."id" ASC LIMIT ${} OFFSET ${}"#,
+            n + 1,
+            n + 2
+        ),
+        values.into_iter().chain([21u64.into(), 0u64.into()]),
+    ))
+}
+
+#[actix_web::test]
+async fn get_only_requested_fields() {
+    let (status, body) = get_profile("/profiles/1?fields=name").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "name": "Ann" }));
+}
+
+#[actix_web::test]
+async fn get_without_omitted_fields() {
+    let (status, body) = get_profile("/profiles/1?omit=bio,id").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "name": "Ann" }));
+}
+
+#[actix_web::test]
+async fn list_every_field() {
+    let (items, query) = list_profiles("/profiles").await;
+    assert_eq!(items, json!([{ "id": 1, "name": "Ann", "bio": "Hello" }]));
+    assert_eq!(
+        query,
+        list_profiles_sql(
+            r#""profiles"."id", "profiles"."name", "profiles"."bio""#,
+            vec![]
+        )
+    );
+}
+
+#[actix_web::test]
+async fn list_only_requested_fields() {
+    let (items, query) = list_profiles("/profiles?fields=name").await;
+    assert_eq!(items, json!([{ "name": "Ann" }]));
+    // The primary key is loaded regardless, and the bio is not loaded at all.
+    assert_eq!(
+        query,
+        list_profiles_sql(
+            r#""profiles"."id", "profiles"."name", $1 AS "bio""#,
+            vec!["".into()]
+        )
+    );
+}
+
+#[actix_web::test]
+async fn list_without_omitted_fields() {
+    let (items, query) = list_profiles("/profiles?omit=bio,id").await;
+    assert_eq!(items, json!([{ "name": "Ann" }]));
+    assert_eq!(
+        query,
+        list_profiles_sql(
+            r#""profiles"."id", "profiles"."name", $1 AS "bio""#,
+            vec!["".into()]
+        )
+    );
+}
+
+#[actix_web::test]
+async fn list_fields_other_than_columns() {
+    let (items, query) = list_profiles("/profiles?fields=name,age").await;
+    assert_eq!(items, json!([{ "name": "Ann" }]));
+    assert_eq!(
+        query,
+        list_profiles_sql(
+            r#""profiles"."id", "profiles"."name", $1 AS "bio""#,
+            vec!["".into()]
+        )
+    );
+}
+
+#[actix_web::test]
+async fn list_fields_of_repr_other_than_model() {
+    // Any column may be needed for a field of the `Repr`, so every column is loaded.
+    for uri in ["/profiles?fields=about", "/profiles?omit=bio"] {
+        let (items, query) = list::<profiles::SummaryRestModel>(uri).await;
+        assert_eq!(items[0]["about"], "Hello", "{uri}");
+        assert_eq!(
+            query,
+            list_profiles_sql(
+                r#""profiles"."id", "profiles"."name", "profiles"."bio""#,
+                vec![]
+            ),
+            "{uri}"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use actix_web::web;
+use sea_orm::{ColumnType, DbBackend, EntityTrait, QueryFilter, QueryTrait};
+use woof::{Filter, Lookup};
+
+mod posts {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::Filter;
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "posts")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub title: String,
+        pub score: i32,
+        pub summary: Option<String>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Default, Deserialize, Filter)]
+    pub struct FilterModel {
+        pub limit: Option<usize>,
+        pub offset: Option<usize>,
+        pub cursor: Option<String>,
+        pub title: Option<String>,
+        #[woof(column = "Title", lookup = "icontains")]
+        pub search: Option<String>,
+        #[woof(column = "Id", lookup = "in")]
+        pub ids: Option<Vec<i32>>,
+        #[woof(column = "Score", lookup = "range")]
+        pub score: Option<(i32, i32)>,
+        #[woof(column = "Summary", lookup = "isnull")]
+        pub draft: Option<bool>,
+        #[allow(dead_code)]
+        #[woof(skip)]
+        pub format: Option<String>,
+    }
+}
+
+fn sql<F: Filter>(filter: &F) -> String {
+    posts::Entity::find()
+        .filter(filter.condition())
+        .build(DbBackend::Postgres)
+        .to_string()
+}
+
+fn select(condition: &str) -> String {
+    let columns = r#""posts"."id", "posts"."title", "posts"."score", "posts"."summary""#;
+    match condition {
+        "" => format!(r#"SELECT {} FROM "posts""#, columns),
+        condition => format!(r#"SELECT {} FROM "posts" WHERE {}"#, columns, condition),
+    }
+}
+
+#[test]
+fn derived_filter_without_values() {
+    let filter = posts::FilterModel {
+        format: Some("csv".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(sql(&filter), select(""));
+    assert_eq!(filter.limit(), 20);
+    assert_eq!(filter.offset(), 0);
+    assert_eq!(filter.page(), 0);
+    assert_eq!(filter.cursor(), None);
+}
+
+#[test]
+fn derived_filter_pagination_fields() {
+    let filter = posts::FilterModel {
+        limit: Some(5),
+        offset: Some(10),
+        cursor: Some("abc".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(sql(&filter), select(""));
+    assert_eq!(filter.limit(), 5);
+    assert_eq!(filter.offset(), 10);
+    assert_eq!(filter.cursor(), Some("abc"));
+}
+
+#[test]
+fn derived_filter_defaults_to_exact() {
+    let filter = posts::FilterModel {
+        title: Some("Hello".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(sql(&filter), select(r#""posts"."title" = 'Hello'"#));
+}
+
+#[test]
+fn derived_filter_lookups() {
+    let filter = posts::FilterModel {
+        search: Some("Rust".to_owned()),
+        ids: Some(vec![1, 2]),
+        score: Some((3, 7)),
+        draft: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(
+        sql(&filter),
+        select(concat!(
+            r#"LOWER("posts"."title") LIKE '%rust%' AND "posts"."id" IN (1, 2) AND "#,
+            r#"("posts"."score" BETWEEN 3 AND 7) AND "posts"."summary" IS NULL"#,
+        ))
+    );
+}
+
+fn lookup_filter(query: &str) -> Result<woof::LookupFilter<posts::Entity>, String> {
+    web::Query::from_query(query)
+        .map(web::Query::into_inner)
+        .map_err(|error| error.to_string())
+}
+
+#[test]
+fn lookup_parse() {
+    let column_type = ColumnType::Integer;
+    assert_eq!(
+        Lookup::parse("gte", &column_type, "3").unwrap(),
+        Lookup::Gte(3.into())
+    );
+    assert_eq!(
+        Lookup::parse("in", &column_type, "1,2").unwrap(),
+        Lookup::In(vec![1.into(), 2.into()])
+    );
+    assert_eq!(
+        Lookup::parse("range", &column_type, "1,5").unwrap(),
+        Lookup::Range(1.into(), 5.into())
+    );
+    assert_eq!(
+        Lookup::parse("isnull", &column_type, "false").unwrap(),
+        Lookup::IsNull(false)
+    );
+}
+
+#[test]
+fn lookup_parse_rejects_invalid_values() {
+    let column_type = ColumnType::Integer;
+    let error = |name, raw| {
+        Lookup::parse(name, &column_type, raw)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error("gt", "ten"),
+        "woof::Error: Invalid value ten for lookup gt"
+    );
+    assert_eq!(
+        error("in", "1,two"),
+        "woof::Error: Invalid value two for lookup in"
+    );
+    assert_eq!(
+        error("range", "1"),
+        "woof::Error: Invalid value 1 for lookup range"
+    );
+    assert_eq!(
+        error("isnull", "maybe"),
+        "woof::Error: Invalid value maybe for lookup isnull"
+    );
+    assert_eq!(error("like", "1"), "woof::Error: Unknown lookup like");
+}
+
+#[test]
+fn lookup_condition() {
+    let condition = |lookup: Lookup, column| {
+        posts::Entity::find()
+            .filter(lookup.condition(column))
+            .build(DbBackend::Postgres)
+            .to_string()
+    };
+    assert_eq!(
+        condition(Lookup::IExact("Hello".into()), posts::Column::Title),
+        select(r#"LOWER("posts"."title") = 'hello'"#)
+    );
+    assert_eq!(
+        condition(Lookup::StartsWith("50%_".into()), posts::Column::Title),
+        select(r#""posts"."title" LIKE E'50\\%\\_%'"#)
+    );
+    assert_eq!(
+        condition(Lookup::IEndsWith("Rust".into()), posts::Column::Title),
+        select(r#"LOWER("posts"."title") LIKE '%rust'"#)
+    );
+    // Pattern lookups on anything but text compare exactly.
+    assert_eq!(
+        condition(Lookup::Contains(3.into()), posts::Column::Score),
+        select(r#""posts"."score" = 3"#)
+    );
+    assert_eq!(
+        condition(Lookup::IsNull(false), posts::Column::Summary),
+        select(r#""posts"."summary" IS NOT NULL"#)
+    );
+}
+
+#[test]
+fn lookup_filter_reads_query() {
+    for (query, condition) in [
+        (
+            "title__icontains=Rust",
+            r#"LOWER("posts"."title") LIKE '%rust%'"#,
+        ),
+        ("score__gte=3", r#""posts"."score" >= 3"#),
+        ("id__in=1,2", r#""posts"."id" IN (1, 2)"#),
+        ("summary__isnull=false", r#""posts"."summary" IS NOT NULL"#),
+    ] {
+        let filter = lookup_filter(query).unwrap();
+        assert_eq!(sql(&filter), select(condition), "{query}");
+    }
+    let filter = lookup_filter("limit=5&offset=10&page=2&cursor=abc").unwrap();
+    assert_eq!(sql(&filter), select(""));
+    assert_eq!(filter.limit(), 5);
+    assert_eq!(filter.offset(), 10);
+    assert_eq!(filter.page(), 2);
+    assert_eq!(filter.cursor(), Some("abc"));
+}
+
+#[test]
+fn lookup_filter_defaults_to_exact() {
+    let filter = lookup_filter("title=Hello").unwrap();
+    assert_eq!(sql(&filter), select(r#""posts"."title" = 'Hello'"#));
+    assert_eq!(filter.limit(), 20);
+    assert_eq!(filter.offset(), 0);
+    assert_eq!(filter.cursor(), None);
+}
+
+#[test]
+fn lookup_filter_rejects_unknown_fields() {
+    assert_eq!(
+        lookup_filter("author=1").err(),
+        Some("Query deserialize error: Unknown field author".to_owned())
+    );
+    // A suffix that is not a lookup is part of the field name.
+    assert_eq!(
+        lookup_filter("title__like=Rust").err(),
+        Some("Query deserialize error: Unknown field title__like".to_owned())
+    );
+}
+
+#[test]
+fn lookup_filter_rejects_invalid_values() {
+    assert_eq!(
+        lookup_filter("score__lt=ten").err(),
+        Some("Query deserialize error: Invalid value ten for lookup lt".to_owned())
+    );
+    assert_eq!(
+        lookup_filter("limit=all").err(),
+        Some("Query deserialize error: Invalid value all for limit".to_owned())
+    );
+}
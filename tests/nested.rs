@@ -0,0 +1,262 @@
+use actix_web::http::StatusCode;
+use actix_web::test;
+use sea_orm::{DbBackend, MockDatabase, Transaction, Value};
+use serde_json::json;
+use std::collections::BTreeMap;
+use woof::RestModel;
+
+mod common;
+
+mod posts {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "posts")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub title: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {
+        #[sea_orm(has_many = "super::comments::Entity")]
+        Comments,
+    }
+
+    impl Related<super::comments::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Comments.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub title: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub title: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>")]
+    pub struct RestModel;
+}
+
+mod comments {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "comments")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub post: i32,
+        pub body: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::posts::Entity",
+            from = "Column::Post",
+            to = "super::posts::Column::Id"
+        )]
+        Post,
+    }
+
+    impl Related<super::posts::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Post.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub body: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub body: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>")]
+    pub struct RestModel;
+}
+
+/// Sends `request` to the comments nested beneath posts.
+async fn call(
+    db: MockDatabase,
+    request: test::TestRequest,
+) -> (StatusCode, serde_json::Value, Vec<Transaction>) {
+    let resource = RestModel::<posts::RestModel>::new("/posts")
+        .nest(RestModel::<comments::RestModel>::new("/comments"));
+    common::call(resource.as_service(), db, request).await
+}
+
+/// Sends `request` to the comments related to posts.
+async fn call_related(
+    db: MockDatabase,
+    request: test::TestRequest,
+) -> (StatusCode, serde_json::Value, Vec<Transaction>) {
+    let resource = RestModel::<posts::RestModel>::new("/posts").related::<comments::RestModel>();
+    common::call(resource.as_service(), db, request).await
+}
+
+fn post() -> posts::Model {
+    posts::Model {
+        id: 1,
+        title: "Existing".to_owned(),
+    }
+}
+
+fn comment() -> comments::Model {
+    comments::Model {
+        id: 1,
+        post: 1,
+        body: "Nice".to_owned(),
+    }
+}
+
+/// The query for the post with id 1, the parent of the nested comments.
+fn find_parent() -> Transaction {
+    Transaction::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT "posts"."id", "posts"."title" FROM "posts" WHERE "posts"."id" = $1 LIMIT $2"#,
+        vec![1i32.into(), 1u64.into()],
+    )
+}
+
+#[actix_web::test]
+async fn nested_row_of_parent() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![post()]])
+        .append_query_results(vec![vec![comment()]]);
+    let request = test::TestRequest::get().uri("/posts/1/comments/1");
+    let (status, body, log) = call(db, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "id": 1, "post": 1, "body": "Nice" }));
+    assert_eq!(
+        log,
+        vec![
+            find_parent(),
+            Transaction::from_sql_and_values(
+                DbBackend::Postgres,
+                concat!(
+                    r#"SELECT "comments"."id", "comments"."post", "comments"."body" FROM "comments" "#,
+                    r#"INNER JOIN "posts" ON "posts"."id" = "comments"."post" "#,
+                    r#"WHERE "posts"."id" = $1 AND "comments"."id" = $2 LIMIT $3"#,
+                ),
+                vec![1i32.into(), 1i32.into(), 1u64.into()],
+            ),
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn nested_beneath_missing_parent() {
+    for request in [
+        test::TestRequest::get().uri("/posts/1/comments"),
+        test::TestRequest::get().uri("/posts/1/comments/1"),
+        test::TestRequest::post()
+            .uri("/posts/1/comments")
+            .set_json(json!({ "body": "Nice" })),
+    ] {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results(vec![Vec::<posts::Model>::new()]);
+        let (status, _, log) = call(db, request).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(log, vec![find_parent()]);
+    }
+}
+
+#[actix_web::test]
+async fn create_nested_row_of_parent() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![post()]])
+        .append_query_results(vec![vec![comment()]]);
+    let request = test::TestRequest::post()
+        .uri("/posts/1/comments")
+        .set_json(json!({ "body": "Nice" }));
+    let (status, body, log) = call(db, request).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body, json!({ "id": 1, "post": 1, "body": "Nice" }));
+    assert_eq!(
+        log[1],
+        Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO "comments" ("post", "body") VALUES ($1, $2) RETURNING "id", "post", "body""#,
+            vec![1i32.into(), "Nice".into()],
+        )
+    );
+}
+
+#[actix_web::test]
+async fn related_rows_of_parent() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![post()]])
+        .append_query_results(vec![vec![BTreeMap::from([(
+            "num_items",
+            Value::from(1i64),
+        )])]])
+        .append_query_results(vec![vec![comment()]]);
+    let request = test::TestRequest::get().uri("/posts/1/comments?limit=10");
+    let (status, body, log) = call_related(db, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["items"],
+        json!([{ "id": 1, "post": 1, "body": "Nice" }])
+    );
+    assert_eq!(body["total"], 1);
+    assert_eq!(log.len(), 3);
+    assert_eq!(log[0], find_parent());
+    assert_eq!(
+        log[2],
+        Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            concat!(
+                r#"SELECT "comments"."id", "comments"."post", "comments"."body" FROM "comments" "#,
+                r#"INNER JOIN "posts" ON "posts"."id" = "comments"."post" "#,
+                r#"WHERE "posts"."id" = $1 ORDER BY "comments"."id" ASC LIMIT $2 OFFSET $3"#,
+            ),
+            vec![1i32.into(), 11u64.into(), 0u64.into()],
+        )
+    );
+}
+
+#[actix_web::test]
+async fn related_rows_beneath_missing_parent() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![Vec::<posts::Model>::new()]);
+    let request = test::TestRequest::get().uri("/posts/1/comments");
+    let (status, _, log) = call_related(db, request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(log, vec![find_parent()]);
+}
+
+#[actix_web::test]
+async fn related_rows_are_read_only() {
+    for request in [
+        test::TestRequest::post()
+            .uri("/posts/1/comments")
+            .set_json(json!({ "body": "Nice" })),
+        test::TestRequest::delete().uri("/posts/1/comments/1"),
+    ] {
+        let db = MockDatabase::new(DbBackend::Postgres);
+        let (status, _, log) = call_related(db, request).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(log, vec![]);
+    }
+}
@@ -0,0 +1,99 @@
+use actix_web::http::StatusCode;
+use actix_web::test;
+use sea_orm::{DbBackend, MockDatabase, Transaction, Value};
+use std::collections::BTreeMap;
+use woof::RestModel;
+
+mod common;
+
+mod posts {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "posts")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub title: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub title: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub title: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        orderable = "title",
+        ordering = "-title"
+    )]
+    pub struct RestModel;
+}
+
+/// Lists the posts at `uri`, returning the response and the query that found the page.
+async fn list(uri: &str) -> (StatusCode, serde_json::Value, Option<Transaction>) {
+    let resource = RestModel::<posts::RestModel>::new("/posts");
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![BTreeMap::from([(
+            "num_items",
+            Value::from(0i64),
+        )])]])
+        .append_query_results(vec![Vec::<posts::Model>::new()]);
+    let request = test::TestRequest::get().uri(uri);
+    let (status, body, mut log) = common::call(resource.as_service(), db, request).await;
+    (status, body, log.pop())
+}
+
+fn ordered_by(order_by: &str) -> Option<Transaction> {
+    Some(Transaction::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            r#"SELECT "posts"."id", "posts"."title" FROM "posts" ORDER BY {order_by} LIMIT $1 OFFSET $2"#
+        ),
+        vec![21u64.into(), 0u64.into()],
+    ))
+}
+
+#[actix_web::test]
+async fn list_in_default_ordering() {
+    let (status, _, query) = list("/posts").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        query,
+        ordered_by(r#""posts"."title" DESC, "posts"."id" ASC"#)
+    );
+}
+
+#[actix_web::test]
+async fn list_in_requested_ordering() {
+    let (status, _, query) = list("/posts?ordering=title").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        query,
+        ordered_by(r#""posts"."title" ASC, "posts"."id" ASC"#)
+    );
+}
+
+#[actix_web::test]
+async fn list_in_ordering_outside_whitelist() {
+    for (ordering, field) in [("-id", "-id"), ("title,-id", "-id"), ("body", "body")] {
+        let uri = format!("/posts?ordering={ordering}");
+        let (status, body, query) = list(&uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{ordering}");
+        assert_eq!(body["detail"], format!("Cannot order by {field}"));
+        assert_eq!(query, None);
+    }
+}
@@ -0,0 +1,93 @@
+use sea_orm::{ActiveValue, IntoActiveModel};
+use serde_json::json;
+use woof::Patch;
+
+mod profiles {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Patch, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "profiles")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub name: String,
+        pub bio: Option<String>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub name: String,
+        #[serde(default)]
+        pub bio: Patch<String>,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub name: Option<String>,
+        #[serde(default)]
+        pub bio: Patch<String>,
+    }
+}
+
+fn update(body: serde_json::Value) -> profiles::ActiveModel {
+    serde_json::from_value::<profiles::UpdateModel>(body)
+        .unwrap()
+        .into_active_model()
+}
+
+#[test]
+fn patch_from_json() {
+    let patch = |body| {
+        serde_json::from_value::<profiles::UpdateModel>(body)
+            .unwrap()
+            .bio
+    };
+    assert_eq!(patch(json!({})), Patch::Absent);
+    assert_eq!(patch(json!({ "bio": null })), Patch::Null);
+    assert_eq!(
+        patch(json!({ "bio": "Hello" })),
+        Patch::Value("Hello".to_owned())
+    );
+}
+
+#[test]
+fn update_leaves_absent_fields() {
+    let active_model = update(json!({ "name": "Bob" }));
+    assert_eq!(active_model.id, ActiveValue::NotSet);
+    assert_eq!(active_model.name, ActiveValue::Set("Bob".to_owned()));
+    assert_eq!(active_model.bio, ActiveValue::NotSet);
+}
+
+#[test]
+fn update_sets_patch_field_to_null() {
+    let active_model = update(json!({ "bio": null }));
+    assert_eq!(active_model.name, ActiveValue::NotSet);
+    assert_eq!(active_model.bio, ActiveValue::Set(None));
+}
+
+#[test]
+fn update_sets_patch_field_to_value() {
+    let active_model = update(json!({ "bio": "Goodbye" }));
+    assert_eq!(active_model.name, ActiveValue::NotSet);
+    assert_eq!(
+        active_model.bio,
+        ActiveValue::Set(Some("Goodbye".to_owned()))
+    );
+}
+
+#[test]
+fn create_sets_plain_fields() {
+    let active_model = serde_json::from_value::<profiles::CreateModel>(json!({ "name": "Ann" }))
+        .unwrap()
+        .into_active_model();
+    assert_eq!(active_model.id, ActiveValue::NotSet);
+    assert_eq!(active_model.name, ActiveValue::Set("Ann".to_owned()));
+    assert_eq!(active_model.bio, ActiveValue::NotSet);
+}
@@ -0,0 +1,132 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::{test, web, HttpResponse};
+use sea_orm::{DbBackend, MockDatabase};
+use serde_json::json;
+use woof::{ErrorRenderer, RestModel};
+
+mod common;
+
+mod posts {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "posts")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub title: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub title: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub title: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>")]
+    pub struct RestModel;
+}
+
+/// Sends `request` to the posts resource, rendering its errors with `renderer` if given.
+/// Returns the headers as well, which the shared helper leaves out.
+async fn call(
+    db: MockDatabase,
+    renderer: Option<ErrorRenderer>,
+    request: test::TestRequest,
+) -> (StatusCode, header::HeaderMap, serde_json::Value) {
+    let mut scope = web::scope("");
+    if let Some(renderer) = renderer {
+        scope = scope.app_data(web::Data::new(renderer));
+    }
+    let service = scope.service(RestModel::<posts::RestModel>::new("/posts").as_service());
+    let (status, headers, body, _) = common::call_with_headers(service, db, request).await;
+    (status, headers, body)
+}
+
+/// A database without the post looked for.
+fn no_posts() -> MockDatabase {
+    MockDatabase::new(DbBackend::Postgres).append_query_results(vec![Vec::<posts::Model>::new()])
+}
+
+/// Renders problems in an envelope of its own.
+fn enveloped() -> ErrorRenderer {
+    ErrorRenderer::new(|problem| {
+        HttpResponse::build(problem.status_code()).json(json!({
+            "error": {
+                "code": problem.status,
+                "message": problem.detail.as_deref().unwrap_or(&problem.title),
+            }
+        }))
+    })
+}
+
+#[actix_web::test]
+async fn error_as_problem() {
+    let request = test::TestRequest::get().uri("/posts/1");
+    let (status, headers, body) = call(no_posts(), None, request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        headers.get(header::CONTENT_TYPE).unwrap(),
+        "application/problem+json"
+    );
+    assert_eq!(
+        body,
+        json!({
+            "type": "about:blank",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "Not found",
+        })
+    );
+}
+
+#[actix_web::test]
+async fn database_error_as_problem_without_detail() {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::get().uri("/posts/1");
+    let (status, _, body) = call(db, None, request).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        body,
+        json!({ "type": "about:blank", "title": "Internal Server Error", "status": 500 })
+    );
+}
+
+#[actix_web::test]
+async fn error_with_custom_renderer() {
+    let request = test::TestRequest::get().uri("/posts/1");
+    let (status, headers, body) = call(no_posts(), Some(enveloped()), request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        headers.get(header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    assert_eq!(
+        body,
+        json!({ "error": { "code": 404, "message": "Not found" } })
+    );
+}
+
+#[actix_web::test]
+async fn actix_error_with_custom_renderer() {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::post()
+        .uri("/posts")
+        .insert_header((header::CONTENT_TYPE, "text/plain"))
+        .set_payload("title");
+    let (status, _, body) = call(db, Some(enveloped()), request).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(body["error"]["code"], 415);
+}
@@ -0,0 +1,918 @@
+use actix_web::{http::header, http::Method, http::StatusCode, test, web, HttpRequest};
+use sea_orm::{DbBackend, MockDatabase, MockExecResult, Transaction, Value};
+use serde_json::json;
+use std::collections::BTreeMap;
+use woof::{Action, Actions, Hooks, RestModel};
+
+mod common;
+
+mod posts {
+    use actix_web::http::StatusCode;
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{
+        Action, Context, Create, Hooks, Permission, Problem, Rest, Update, Validate,
+        ValidationErrors,
+    };
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "posts")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub title: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {
+        #[sea_orm(has_many = "super::comments::Entity")]
+        Comments,
+    }
+
+    impl Related<super::comments::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Comments.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub title: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    #[woof(validate)]
+    pub struct UpdateModel {
+        pub title: Option<String>,
+    }
+
+    impl Validate for UpdateModel {
+        fn validate(&self, errors: &mut ValidationErrors) {
+            if self.title.as_deref() == Some("") {
+                errors.add("title", "may not be empty");
+            }
+        }
+    }
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        expand = "comments: super::comments::RestModel",
+        expand_limit = "2"
+    )]
+    pub struct RestModel;
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>", idempotent_delete, hooks)]
+    pub struct IdempotentRestModel;
+
+    thread_local! {
+        /// How many times the posts of [`IdempotentRestModel`] were deleted.
+        pub static DELETED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    #[async_trait::async_trait(?Send)]
+    impl Hooks for IdempotentRestModel {
+        async fn after_delete(_ctx: &Context<'_>, _active_model: &ActiveModel) -> woof::Result<()> {
+            DELETED.with(|deleted| deleted.set(deleted.get() + 1));
+            Ok(())
+        }
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>", hooks)]
+    pub struct ArchivedRestModel;
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>", permission = "woof::ReadOnly")]
+    pub struct ReadOnlyRestModel;
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>", permission = "NoDrafts")]
+    pub struct DraftlessRestModel;
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        pagination = "woof::LimitOffsetPagination<Model>",
+        count = "skip"
+    )]
+    pub struct OffsetRestModel;
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        pagination = "woof::PageNumberPagination<Model>",
+        count = "skip"
+    )]
+    pub struct PagedRestModel;
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>", count = "approximate")]
+    pub struct EstimatedRestModel;
+
+    /// Hides the posts titled "Draft".
+    pub struct NoDrafts;
+
+    #[async_trait::async_trait(?Send)]
+    impl<T: Rest<Entity = Entity>> Permission<T> for NoDrafts {
+        async fn has_object_permission(
+            &self,
+            _ctx: &Context<'_>,
+            _action: Action,
+            model: &Model,
+        ) -> woof::Result<bool> {
+            Ok(model.title != "Draft")
+        }
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>", hooks)]
+    pub struct PublishedRestModel;
+
+    impl Hooks for PublishedRestModel {
+        fn base_query(_ctx: &Context<'_>) -> Select<Entity> {
+            Entity::find().filter(Column::Title.ne("Draft"))
+        }
+    }
+
+    #[async_trait::async_trait(?Send)]
+    impl Hooks for ArchivedRestModel {
+        async fn before_delete(
+            _ctx: &Context<'_>,
+            _active_model: &mut ActiveModel,
+        ) -> woof::Result<()> {
+            Err(Problem::new(StatusCode::FORBIDDEN)
+                .with_detail("Archived posts may not be deleted")
+                .into())
+        }
+    }
+}
+
+mod comments {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "comments")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: i32,
+        pub post: i32,
+        pub body: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::posts::Entity",
+            from = "Column::Post",
+            to = "super::posts::Column::Id"
+        )]
+        Post,
+    }
+
+    impl Related<super::posts::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Post.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub body: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub body: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(
+        filter = "woof::LookupFilter<Entity>",
+        expand = "post: super::posts::DraftlessRestModel"
+    )]
+    pub struct RestModel;
+}
+
+mod post_tags {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use woof::{Create, Rest, Update};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "post_tags")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub post: i32,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub tag: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    #[derive(Clone, Serialize, Deserialize, Create)]
+    pub struct CreateModel {
+        pub post: i32,
+        pub tag: String,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Update)]
+    pub struct UpdateModel {
+        pub tag: Option<String>,
+    }
+
+    #[derive(Rest)]
+    #[woof(filter = "woof::LookupFilter<Entity>")]
+    pub struct RestModel;
+}
+
+fn comment() -> comments::Model {
+    comments::Model {
+        id: 1,
+        post: 1,
+        body: "Nice".to_owned(),
+    }
+}
+
+fn post(title: &str) -> posts::Model {
+    posts::Model {
+        id: 1,
+        title: title.to_owned(),
+    }
+}
+
+/// A database whose first query finds the post with id 1, if it exists.
+fn posts(found: bool) -> MockDatabase {
+    let rows = if found {
+        vec![post("Existing")]
+    } else {
+        vec![]
+    };
+    MockDatabase::new(DbBackend::Postgres).append_query_results(vec![rows])
+}
+
+fn deleted(rows_affected: u64) -> Vec<MockExecResult> {
+    vec![MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }]
+}
+
+/// Sends `request` to the posts resource `T`.
+async fn call_posts<T>(
+    db: MockDatabase,
+    request: test::TestRequest,
+) -> (StatusCode, serde_json::Value, Vec<Transaction>)
+where
+    T: Hooks<Entity = posts::Entity, ActiveModel = posts::ActiveModel> + 'static,
+{
+    common::call(RestModel::<T>::new("/posts").as_service(), db, request).await
+}
+
+async fn delete<T>(db: MockDatabase) -> StatusCode
+where
+    T: Hooks<Entity = posts::Entity, ActiveModel = posts::ActiveModel> + 'static,
+{
+    let request = test::TestRequest::delete().uri("/posts/1");
+    call_posts::<T>(db, request).await.0
+}
+
+#[actix_web::test]
+async fn delete_existing_row() {
+    let db = posts(true).append_exec_results(deleted(1));
+    let status = delete::<posts::RestModel>(db).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[actix_web::test]
+async fn delete_missing_row() {
+    let status = delete::<posts::RestModel>(posts(false)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn delete_missing_row_idempotently() {
+    let status = delete::<posts::IdempotentRestModel>(posts(false)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    // Nothing was deleted, so the hook did not run.
+    assert_eq!(posts::DELETED.with(|deleted| deleted.get()), 0);
+
+    let db = posts(true).append_exec_results(deleted(1));
+    let status = delete::<posts::IdempotentRestModel>(db).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(posts::DELETED.with(|deleted| deleted.get()), 1);
+}
+
+#[actix_web::test]
+async fn delete_refused_by_hook() {
+    let status = delete::<posts::ArchivedRestModel>(posts(true)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+async fn update(db: MockDatabase, title: &str) -> (StatusCode, serde_json::Value) {
+    let request = test::TestRequest::patch()
+        .uri("/posts/1")
+        .set_json(json!({ "title": title }));
+    let (status, body, _) = call_posts::<posts::RestModel>(db, request).await;
+    (status, body)
+}
+
+#[actix_web::test]
+async fn update_existing_row() {
+    let db = posts(true).append_query_results(vec![vec![post("Updated")]]);
+    let (status, body) = update(db, "Updated").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "id": 1, "title": "Updated" }));
+}
+
+#[actix_web::test]
+async fn update_missing_row() {
+    let (status, body) = update(posts(false), "Updated").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["status"], 404);
+}
+
+#[actix_web::test]
+async fn update_invalid_row() {
+    let (status, body) = update(posts(true), "").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errors"], json!({ "title": ["may not be empty"] }));
+}
+
+#[actix_web::test]
+async fn update_with_invalid_body() {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::patch()
+        .uri("/posts/1")
+        .set_json(json!({ "title": 1 }));
+    let (status, body, _) = call_posts::<posts::RestModel>(db, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["errors"],
+        json!({ "title": ["invalid type: integer `1`, expected a string"] })
+    );
+}
+
+#[actix_web::test]
+async fn update_with_duplicate_field() {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::patch()
+        .uri("/posts/1")
+        .insert_header((header::CONTENT_TYPE, "application/json"))
+        .set_payload("\n{\n  \"title\": \"First\",\n  \"title\": \"Second\"\n}");
+    let (status, body, _) = call_posts::<posts::RestModel>(db, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["errors"],
+        json!({ "title": ["duplicate field `title`"] })
+    );
+}
+
+#[actix_web::test]
+async fn update_with_body_over_json_limit() {
+    let service = web::scope("")
+        .app_data(web::JsonConfig::default().limit(8))
+        .service(RestModel::<posts::RestModel>::new("/posts").as_service());
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::patch()
+        .uri("/posts/1")
+        .set_json(json!({ "title": "Longer than the limit" }));
+    let (status, _, _) = common::call(service, db, request).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[actix_web::test]
+async fn update_with_body_of_other_type() {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::patch()
+        .uri("/posts/1")
+        .insert_header((header::CONTENT_TYPE, "text/plain"))
+        .set_payload(r#"{ "title": "Updated" }"#);
+    let (status, _, _) = call_posts::<posts::RestModel>(db, request).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[actix_web::test]
+async fn list_with_invalid_first_parameter() {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::get().uri("/posts?limit=ten&title=a");
+    let (status, body, _) = call_posts::<posts::RestModel>(db, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["errors"],
+        json!({ "limit": ["Invalid value ten for limit"] })
+    );
+}
+
+#[actix_web::test]
+async fn list_with_invalid_query() {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let request = test::TestRequest::get().uri("/posts?title=a&id__gte=one");
+    let (status, body, _) = call_posts::<posts::RestModel>(db, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["errors"],
+        json!({ "id__gte": ["Invalid value one for lookup gte"] })
+    );
+}
+
+async fn replace(db: MockDatabase) -> (StatusCode, serde_json::Value) {
+    let (status, body, _) =
+        replace_at::<posts::RestModel>(db, "/posts", "/1", json!({ "title": "Replaced" })).await;
+    (status, body)
+}
+
+/// Replaces the row at `id` of the resource at `path`, returning the statement that
+/// wrote it as well.
+async fn replace_at<T>(
+    db: MockDatabase,
+    path: &str,
+    id: &str,
+    body: serde_json::Value,
+) -> (StatusCode, serde_json::Value, Option<Transaction>)
+where
+    T: Hooks + 'static,
+    <T::Entity as sea_orm::EntityTrait>::Model:
+        sea_orm::IntoActiveModel<T::ActiveModel> + serde::de::DeserializeOwned + Send + Sync,
+    <<T::Entity as sea_orm::EntityTrait>::PrimaryKey as sea_orm::PrimaryKeyTrait>::ValueType:
+        serde::de::DeserializeOwned + Clone,
+{
+    let request = test::TestRequest::put()
+        .uri(&format!("{path}{id}"))
+        .set_json(body);
+    let (status, body, mut log) =
+        common::call(RestModel::<T>::new(path).as_service(), db, request).await;
+    (status, body, log.pop())
+}
+
+/// The row returned by an upsert.
+fn upserted(inserted: bool) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("id", Value::from(1)),
+        ("title", Value::from("Replaced")),
+        ("woof_inserted", Value::from(inserted)),
+    ])
+}
+
+/// The upsert of the post with id 1, titled "Replaced".
+fn upsert_post() -> Option<Transaction> {
+    Some(Transaction::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"INSERT INTO "posts" ("id", "title") VALUES ($1, $2) ON CONFLICT ("id") DO UPDATE SET "title" = EXCLUDED."title" RETURNING *, (xmax = 0) AS "woof_inserted""#,
+        vec![1i32.into(), "Replaced".into()],
+    ))
+}
+
+#[actix_web::test]
+async fn replace_missing_row() {
+    let db = posts(false)
+        .append_query_results(vec![Vec::<posts::Model>::new()])
+        .append_query_results(vec![vec![upserted(true)]]);
+    let (status, body, upsert) =
+        replace_at::<posts::RestModel>(db, "/posts", "/1", json!({ "title": "Replaced" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body, json!({ "id": 1, "title": "Replaced" }));
+    assert_eq!(upsert, upsert_post());
+}
+
+#[actix_web::test]
+async fn replace_existing_row() {
+    let db = posts(true).append_query_results(vec![vec![upserted(false)]]);
+    let (status, body, upsert) =
+        replace_at::<posts::RestModel>(db, "/posts", "/1", json!({ "title": "Replaced" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "id": 1, "title": "Replaced" }));
+    assert_eq!(upsert, upsert_post());
+}
+
+#[actix_web::test]
+async fn replace_resets_columns_left_out() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![comment()]])
+        .append_query_results(vec![vec![BTreeMap::from([
+            ("id", Value::from(1)),
+            ("post", Value::from(1)),
+            ("body", Value::from("Replaced")),
+            ("woof_inserted", Value::from(false)),
+        ])]]);
+    let (status, _, upsert) =
+        replace_at::<comments::RestModel>(db, "/comments", "/1", json!({ "body": "Replaced" }))
+            .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        upsert,
+        Some(Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO "comments" ("id", "body") VALUES ($1, $2) ON CONFLICT ("id") DO UPDATE SET "post" = DEFAULT, "body" = EXCLUDED."body" RETURNING *, (xmax = 0) AS "woof_inserted""#,
+            vec![1i32.into(), "Replaced".into()],
+        ))
+    );
+}
+
+#[actix_web::test]
+async fn replace_row_of_only_primary_key() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![Vec::<post_tags::Model>::new(), vec![]])
+        .append_query_results(vec![vec![BTreeMap::from([
+            ("post", Value::from(1)),
+            ("tag", Value::from("rust")),
+            ("woof_inserted", Value::from(true)),
+        ])]]);
+    let (status, _, upsert) = replace_at::<post_tags::RestModel>(
+        db,
+        "/post_tags",
+        "/1/rust",
+        json!({ "post": 1, "tag": "rust" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        upsert,
+        Some(Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO "post_tags" ("post", "tag") VALUES ($1, $2) ON CONFLICT ("post", "tag") DO UPDATE SET "post" = EXCLUDED."post" RETURNING *, (xmax = 0) AS "woof_inserted""#,
+            vec![1i32.into(), "rust".into()],
+        ))
+    );
+}
+
+#[actix_web::test]
+async fn replace_on_other_backend() {
+    let db =
+        MockDatabase::new(DbBackend::Sqlite).append_query_results(vec![vec![post("Existing")]]);
+    let (status, _, write) =
+        replace_at::<posts::RestModel>(db, "/posts", "/1", json!({ "title": "Replaced" })).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(!format!("{write:?}").contains("INSERT"));
+}
+
+#[actix_web::test]
+async fn replace_row_outside_base_query() {
+    let db = posts(false).append_query_results(vec![vec![post("Hidden")]]);
+    let (status, _) = replace(db).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn get_row_outside_base_query() {
+    let request = test::TestRequest::get().uri("/posts/1");
+    let (status, _, log) = call_posts::<posts::PublishedRestModel>(posts(false), request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        log,
+        vec![Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT "posts"."id", "posts"."title" FROM "posts" WHERE "posts"."title" <> $1 AND "posts"."id" = $2 LIMIT $3"#,
+            vec!["Draft".into(), 1i32.into(), 1u64.into()],
+        )]
+    );
+}
+
+async fn not_allowed(
+    resource: RestModel<posts::RestModel>,
+    request: test::TestRequest,
+) -> (StatusCode, Option<String>) {
+    let db = MockDatabase::new(DbBackend::Postgres);
+    let (status, headers, _, _) =
+        common::call_with_headers(resource.as_service(), db, request).await;
+    let allow = headers
+        .get(header::ALLOW)
+        .map(|value| value.to_str().unwrap().to_owned());
+    (status, allow)
+}
+
+#[actix_web::test]
+async fn delete_from_read_only_resource() {
+    let resource = RestModel::new("/posts").actions(Actions::READ_ONLY);
+    let (status, allow) = not_allowed(resource, test::TestRequest::delete().uri("/posts/1")).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(allow.as_deref(), Some("GET, HEAD"));
+}
+
+#[actix_web::test]
+async fn detail_without_detail_actions() {
+    let resource = RestModel::new("/posts").actions(Actions::NONE.with(Action::Create));
+    let (status, allow) = not_allowed(resource, test::TestRequest::get().uri("/posts/1")).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(allow.as_deref(), Some(""));
+}
+
+#[actix_web::test]
+async fn head_of_row() {
+    let request = test::TestRequest::default()
+        .method(Method::HEAD)
+        .uri("/posts/1");
+    let (status, _, _) = call_posts::<posts::RestModel>(posts(true), request).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn replace_without_replace_action() {
+    let resource = RestModel::new("/posts").without(Action::Replace);
+    let request = test::TestRequest::put()
+        .uri("/posts/1")
+        .set_json(json!({ "title": "Replaced" }));
+    let (status, allow) = not_allowed(resource, request).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(allow.as_deref(), Some("GET, HEAD, DELETE, PATCH"));
+}
+
+async fn publish(post: posts::Model, _request: HttpRequest) -> String {
+    format!("Published {}", post.title)
+}
+
+async fn unpublish(post: posts::Model, _request: HttpRequest) -> String {
+    format!("Unpublished {}", post.title)
+}
+
+async fn call_action<T>(
+    db: MockDatabase,
+    request: test::TestRequest,
+) -> (StatusCode, serde_json::Value)
+where
+    T: Hooks<Entity = posts::Entity, ActiveModel = posts::ActiveModel> + 'static,
+{
+    let resource = RestModel::<T>::new("/posts")
+        .detail_action(Method::POST, "publish", publish)
+        .detail_action(Method::DELETE, "publish", unpublish)
+        .collection_action(Method::POST, "archive", || async { "Archived" });
+    let (status, body, _) = common::call(resource.as_service(), db, request).await;
+    (status, body)
+}
+
+#[actix_web::test]
+async fn detail_action_receives_row() {
+    let request = test::TestRequest::post().uri("/posts/1/publish");
+    let (status, body) = call_action::<posts::RestModel>(posts(true), request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "Published Existing");
+}
+
+#[actix_web::test]
+async fn detail_action_on_missing_row() {
+    let request = test::TestRequest::post().uri("/posts/1/publish");
+    let (status, _) = call_action::<posts::RestModel>(posts(false), request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn detail_action_with_another_method() {
+    let request = test::TestRequest::delete().uri("/posts/1/publish");
+    let (status, body) = call_action::<posts::RestModel>(posts(true), request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "Unpublished Existing");
+}
+
+#[actix_web::test]
+async fn custom_actions_share_allowed_methods() {
+    let resource = RestModel::new("/posts")
+        .collection_action(Method::POST, "archive", || async { "Archived" })
+        .collection_action(Method::DELETE, "archive", || async { "Unarchived" });
+    let request = test::TestRequest::get().uri("/posts/archive");
+    let (status, allow) = not_allowed(resource, request).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(allow.as_deref(), Some("POST, DELETE"));
+}
+
+#[actix_web::test]
+async fn collection_action() {
+    let request = test::TestRequest::post().uri("/posts/archive");
+    let (status, body) = call_action::<posts::RestModel>(posts(false), request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "Archived");
+}
+
+#[actix_web::test]
+async fn collection_action_denied_by_permission() {
+    let request = test::TestRequest::post().uri("/posts/archive");
+    let (status, _) = call_action::<posts::ReadOnlyRestModel>(posts(false), request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+async fn create(resource: RestModel<posts::RestModel>, uri: &str) -> (StatusCode, Option<String>) {
+    let db =
+        MockDatabase::new(DbBackend::Postgres).append_query_results(vec![vec![post("Created")]]);
+    let request = test::TestRequest::post()
+        .uri(uri)
+        .set_json(json!({ "title": "Created" }));
+    let (status, headers, _, _) =
+        common::call_with_headers(resource.as_service(), db, request).await;
+    let location = headers
+        .get(header::LOCATION)
+        .map(|value| value.to_str().unwrap().to_owned());
+    (status, location)
+}
+
+#[actix_web::test]
+async fn create_in_collection() {
+    let (status, location) = create(RestModel::new("/posts"), "/posts").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(location.as_deref(), Some("/posts/1"));
+}
+
+#[actix_web::test]
+async fn create_with_new_alias() {
+    let resource = RestModel::new("/posts").with_new_alias();
+    let (status, location) = create(resource, "/posts/new").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(location.as_deref(), Some("/posts/1"));
+}
+
+#[actix_web::test]
+async fn create_without_new_alias() {
+    let (status, _) = create(RestModel::new("/posts"), "/posts/new").await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+}
+
+async fn get_comment(post_title: &str) -> serde_json::Value {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![comment()]])
+        .append_query_results(vec![vec![post(post_title)]]);
+    let resource = RestModel::<comments::RestModel>::new("/comments");
+    let request = test::TestRequest::get().uri("/comments/1?expand=post");
+    let (_, body, _) = common::call(resource.as_service(), db, request).await;
+    body
+}
+
+#[actix_web::test]
+async fn expand_related_row() {
+    let body = get_comment("Published").await;
+    assert_eq!(body["post"], json!({ "id": 1, "title": "Published" }));
+}
+
+#[actix_web::test]
+async fn expand_leaves_out_rows_refused_by_permission() {
+    let body = get_comment("Draft").await;
+    assert_eq!(body["post"], json!(null));
+}
+
+/// Gets the post with id 1, whose comments are `comments`, from `uri`.
+async fn get_post(uri: &str, comments: Vec<comments::Model>) -> (StatusCode, serde_json::Value) {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![post("Existing")]])
+        .append_query_results(vec![comments]);
+    let request = test::TestRequest::get().uri(uri);
+    let (status, body, _) = call_posts::<posts::RestModel>(db, request).await;
+    (status, body)
+}
+
+fn comments(n: i32) -> Vec<comments::Model> {
+    (1..=n)
+        .map(|id| comments::Model { id, ..comment() })
+        .collect()
+}
+
+#[actix_web::test]
+async fn expand_related_rows() {
+    let (status, body) = get_post("/posts/1?expand=comments", comments(2)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["comments"],
+        json!([
+            { "id": 1, "post": 1, "body": "Nice" },
+            { "id": 2, "post": 1, "body": "Nice" },
+        ])
+    );
+}
+
+#[actix_web::test]
+async fn expand_no_related_rows() {
+    let (status, body) = get_post("/posts/1?expand=comments", vec![]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["comments"], json!([]));
+}
+
+#[actix_web::test]
+async fn expand_too_many_related_rows() {
+    let (status, body) = get_post("/posts/1?expand=comments", comments(3)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["detail"], "Expansions may not find more than 2 rows");
+}
+
+#[actix_web::test]
+async fn expand_unknown_relation() {
+    let (status, _) = get_post("/posts/1?expand=author", vec![]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn expand_too_deep() {
+    let (status, _) = get_post("/posts/1?expand=comments.post", vec![]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn nested_beneath_parent_refused_by_permission() {
+    let db = MockDatabase::new(DbBackend::Postgres).append_query_results(vec![vec![post("Draft")]]);
+    let resource =
+        RestModel::<posts::DraftlessRestModel>::new("/posts")
+            .nest(RestModel::<comments::RestModel>::new("/comments"));
+    let request = test::TestRequest::get().uri("/posts/1/comments");
+    let (status, _, _) = common::call(resource.as_service(), db, request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn page_links_ignore_host() {
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![vec![post("First"), post("Second")]]);
+    let request = test::TestRequest::get()
+        .uri("/posts?title=First&limit=1")
+        .insert_header((header::HOST, "not a host"));
+    let (_, body, _) = call_posts::<posts::OffsetRestModel>(db, request).await;
+    assert_eq!(body["next"], "/posts?title=First&limit=1&offset=1");
+    assert_eq!(body["previous"], json!(null));
+}
+
+#[actix_web::test]
+async fn page_out_of_range() {
+    for uri in [
+        format!("/posts?page={}&limit=10", usize::MAX),
+        format!("/posts?page=1&limit={}", usize::MAX),
+    ] {
+        let db = MockDatabase::new(DbBackend::Postgres);
+        let request = test::TestRequest::get().uri(&uri);
+        let (status, _, log) = call_posts::<posts::PagedRestModel>(db, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(log, vec![], "{uri}");
+    }
+}
+
+/// Lists the posts titled "First", estimating how many there are from a query plan that
+/// reads `plan`.
+async fn list_estimated(
+    plan: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value, Vec<Transaction>) {
+    let plan = plan.map(|plan| BTreeMap::from([("QUERY PLAN", Value::from(plan))]));
+    let db = MockDatabase::new(DbBackend::Postgres)
+        .append_query_results(vec![plan.into_iter().collect::<Vec<_>>()])
+        .append_query_results(vec![vec![post("First")]]);
+    let resource = RestModel::<posts::EstimatedRestModel>::new("/posts");
+    let request = test::TestRequest::get().uri("/posts?title=First");
+    common::call(resource.as_service(), db, request).await
+}
+
+#[actix_web::test]
+async fn approximate_count_from_query_plan() {
+    let plan = json!([{ "Plan": { "Node Type": "Seq Scan", "Plan Rows": 42 } }]);
+    let (status, body, log) = list_estimated(Some(plan)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 42);
+    assert_eq!(
+        log[0],
+        Transaction::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"EXPLAIN (FORMAT JSON) SELECT "posts"."id", "posts"."title" FROM "posts" WHERE "posts"."title" = $1"#,
+            vec!["First".into()],
+        )
+    );
+}
+
+#[actix_web::test]
+async fn approximate_count_without_query_plan() {
+    for plan in [
+        None,
+        Some(json!([])),
+        Some(json!([{ "Plan": { "Node Type": "Seq Scan" } }])),
+        Some(json!({ "Plan Rows": 42 })),
+    ] {
+        let (status, body, _) = list_estimated(plan.clone()).await;
+        assert_eq!(status, StatusCode::OK, "{plan:?}");
+        assert_eq!(body["total"], json!(null), "{plan:?}");
+        assert_eq!(
+            body["items"],
+            json!([{ "id": 1, "title": "First" }]),
+            "{plan:?}"
+        );
+    }
+}
+
+#[actix_web::test]
+async fn create_with_composite_key() {
+    let db =
+        MockDatabase::new(DbBackend::Postgres).append_query_results(vec![vec![post_tags::Model {
+            post: 1,
+            tag: "rust/web dev".to_owned(),
+        }]]);
+    let resource = RestModel::<post_tags::RestModel>::new("/post_tags");
+    let request = test::TestRequest::post()
+        .uri("/post_tags")
+        .set_json(json!({ "post": 1, "tag": "rust/web dev" }));
+    let (status, headers, _, _) =
+        common::call_with_headers(resource.as_service(), db, request).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        headers.get(header::LOCATION).unwrap(),
+        "/post_tags/1/rust%2Fweb%20dev"
+    );
+}
@@ -0,0 +1,123 @@
+use crate::attributes::Attributes;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse_quote, Attribute, Data, DataStruct, DeriveInput, Fields, Ident, Meta, NestedMeta, Type,
+};
+
+/// Derives `IntoActiveModel` along with the given marker trait (`Create` or `Update`).
+///
+/// Plain fields are always set, `Option` fields are only set when present, and `Patch`
+/// fields may additionally be set to `NULL`.
+///
+/// `Validate` is implemented with no checks, unless the struct is marked
+/// `#[woof(validate)]` to implement it by hand.
+pub(crate) fn derive(input: DeriveInput, marker: Ident) -> syn::Result<TokenStream> {
+    let attributes = Attributes::parse(&input.attrs)?;
+    attributes.allow(&["active_model", "validate"])?;
+    let active_model: Type = attributes
+        .parse_value("active_model")?
+        .unwrap_or_else(|| parse_quote!(ActiveModel));
+    let container_default = has_serde_default(&input.attrs)?;
+
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => fields.named,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                format!("{marker} can only be derived for structs with named fields"),
+            ))
+        }
+    };
+
+    let mut assignments = vec![];
+    for field in fields {
+        let attributes = Attributes::parse(&field.attrs)?;
+        attributes.allow(&["skip"])?;
+        if attributes.has("skip") {
+            continue;
+        }
+        let ident = field.ident.unwrap();
+        let assignment = match wrapper(&field.ty).as_deref() {
+            Some("Option") => quote! {
+                if let ::std::option::Option::Some(value) = self.#ident {
+                    active_model.#ident = ::woof::__private::sea_orm::ActiveValue::Set(value);
+                }
+            },
+            Some("Patch") => {
+                if !container_default && !has_serde_default(&field.attrs)? {
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "Patch fields must be marked #[serde(default)] so that absent fields can be told apart from null ones",
+                    ));
+                }
+                quote! {
+                    active_model.#ident = self.#ident.into_active_value();
+                }
+            }
+            _ => quote! {
+                active_model.#ident = ::woof::__private::sea_orm::ActiveValue::Set(self.#ident);
+            },
+        };
+        assignments.push(assignment);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let validate = if attributes.has("validate") {
+        quote!()
+    } else {
+        quote! {
+            impl #impl_generics ::woof::Validate for #ident #ty_generics #where_clause {}
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::woof::__private::sea_orm::IntoActiveModel<#active_model>
+            for #ident #ty_generics #where_clause
+        {
+            fn into_active_model(self) -> #active_model {
+                let mut active_model =
+                    <#active_model as ::woof::__private::sea_orm::ActiveModelTrait>::default();
+                #(#assignments)*
+                active_model
+            }
+        }
+
+        impl #impl_generics ::woof::#marker<#active_model> for #ident #ty_generics #where_clause {}
+
+        #validate
+    })
+}
+
+/// The name of the outermost type of a field, if it is one that changes how the field is set.
+fn wrapper(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .filter(|name| name == "Option" || name == "Patch"),
+        _ => None,
+    }
+}
+
+fn has_serde_default(attrs: &[Attribute]) -> syn::Result<bool> {
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("serde")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            let default = list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("default"),
+                NestedMeta::Meta(Meta::NameValue(name_value)) => name_value.path.is_ident("default"),
+                _ => false,
+            });
+            if default {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,62 @@
+use syn::parse::Parse;
+use syn::{Attribute, Lit, LitStr, Meta, NestedMeta, Path};
+
+/// The contents of every `#[woof(...)]` attribute on an item, flattened.
+///
+/// Each entry is either a flag (`#[woof(skip)]`) or a string assignment
+/// (`#[woof(entity = "Entity")]`).
+pub(crate) struct Attributes(Vec<(Path, Option<LitStr>)>);
+
+impl Attributes {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut entries = vec![];
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("woof")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(syn::Error::new_spanned(meta, "expected #[woof(...)]")),
+            };
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) => entries.push((path, None)),
+                    NestedMeta::Meta(Meta::NameValue(name_value)) => match name_value.lit {
+                        Lit::Str(value) => entries.push((name_value.path, Some(value))),
+                        lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
+                    },
+                    nested => {
+                        return Err(syn::Error::new_spanned(nested, "unexpected woof attribute"))
+                    }
+                }
+            }
+        }
+        Ok(Self(entries))
+    }
+
+    /// Fails on the first attribute whose name is not in `known`.
+    pub(crate) fn allow(&self, known: &[&str]) -> syn::Result<()> {
+        for (path, _) in &self.0 {
+            if !known.iter().any(|name| path.is_ident(name)) {
+                return Err(syn::Error::new_spanned(path, "unknown woof attribute"));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn has(&self, name: &str) -> bool {
+        self.0.iter().any(|(path, _)| path.is_ident(name))
+    }
+
+    pub(crate) fn get(&self, name: &str) -> syn::Result<Option<&LitStr>> {
+        match self.0.iter().find(|(path, _)| path.is_ident(name)) {
+            Some((_, Some(value))) => Ok(Some(value)),
+            Some((path, None)) => Err(syn::Error::new_spanned(
+                path,
+                format!("expected `{name} = \"...\"`"),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn parse_value<T: Parse>(&self, name: &str) -> syn::Result<Option<T>> {
+        self.get(name)?.map(LitStr::parse).transpose()
+    }
+}
@@ -0,0 +1,155 @@
+use crate::attributes::Attributes;
+use heck::CamelCase;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_quote, Data, DataStruct, DeriveInput, Fields, Ident, LitInt, Path};
+
+/// The lookups which compare against a single value, and the `woof::Lookup` variant
+/// for each. The remaining lookups (`in`, `isnull` and `range`) are handled separately
+/// as they take a `Vec`, a `bool` and a pair of values respectively.
+const SCALAR_LOOKUPS: [(&str, &str); 12] = [
+    ("exact", "Exact"),
+    ("iexact", "IExact"),
+    ("contains", "Contains"),
+    ("icontains", "IContains"),
+    ("startswith", "StartsWith"),
+    ("istartswith", "IStartsWith"),
+    ("endswith", "EndsWith"),
+    ("iendswith", "IEndsWith"),
+    ("gt", "Gt"),
+    ("gte", "Gte"),
+    ("lt", "Lt"),
+    ("lte", "Lte"),
+];
+
+pub(crate) fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let attributes = Attributes::parse(&input.attrs)?;
+    attributes.allow(&["columns", "default_limit"])?;
+    let columns: Path = attributes
+        .parse_value("columns")?
+        .unwrap_or_else(|| parse_quote!(Column));
+    let default_limit: LitInt = attributes
+        .parse_value("default_limit")?
+        .unwrap_or_else(|| parse_quote!(20));
+
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => fields.named,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "Filter can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let mut limit = quote!(#default_limit);
+    let mut offset = quote!(0);
+    let mut page = quote!(0);
+    let mut cursor = quote!(::std::option::Option::None);
+    let mut ordering = quote!(::std::option::Option::None);
+    let mut conditions = vec![];
+    for field in fields {
+        let ident = field.ident.unwrap();
+        let attributes = Attributes::parse(&field.attrs)?;
+        attributes.allow(&["column", "lookup", "skip"])?;
+        if attributes.has("skip") {
+            continue;
+        }
+        if attributes.get("column")?.is_none() && attributes.get("lookup")?.is_none() {
+            match ident.to_string().as_str() {
+                "limit" => {
+                    limit = quote!(self.limit.unwrap_or(#default_limit));
+                    continue;
+                }
+                "offset" => {
+                    offset = quote!(self.offset.unwrap_or(0));
+                    continue;
+                }
+                "page" => {
+                    page = quote!(self.page.unwrap_or(0));
+                    continue;
+                }
+                "cursor" => {
+                    cursor = quote!(self.cursor.as_deref());
+                    continue;
+                }
+                "ordering" => {
+                    ordering = quote!(self.ordering.as_deref());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        let column: Ident = attributes
+            .parse_value("column")?
+            .unwrap_or_else(|| format_ident!("{}", ident.to_string().to_camel_case()));
+        let lookup = match attributes.get("lookup")? {
+            None => quote!(::woof::Lookup::Exact(::std::convert::Into::into(
+                ::std::clone::Clone::clone(value)
+            ))),
+            Some(name) => match name.value().as_str() {
+                "in" => quote!(::woof::Lookup::In(
+                    value.iter().cloned().map(::std::convert::Into::into).collect()
+                )),
+                "isnull" => quote!(::woof::Lookup::IsNull(*value)),
+                "range" => quote!({
+                    let (start, end) = ::std::clone::Clone::clone(value);
+                    ::woof::Lookup::Range(start.into(), end.into())
+                }),
+                lookup => {
+                    let variant = SCALAR_LOOKUPS
+                        .iter()
+                        .find(|(known, _)| *known == lookup)
+                        .map(|(_, variant)| format_ident!("{}", variant))
+                        .ok_or_else(|| syn::Error::new_spanned(name, "unknown lookup"))?;
+                    quote!(::woof::Lookup::#variant(::std::convert::Into::into(
+                        ::std::clone::Clone::clone(value)
+                    )))
+                }
+            },
+        };
+        conditions.push(quote! {
+            if let ::std::option::Option::Some(value) = &self.#ident {
+                condition = condition.add(#lookup.condition(#columns::#column));
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::woof::Filter for #ident #ty_generics #where_clause {
+            fn limit(&self) -> usize {
+                #limit
+            }
+
+            fn offset(&self) -> usize {
+                #offset
+            }
+
+            fn page(&self) -> usize {
+                #page
+            }
+
+            fn cursor(&self) -> ::std::option::Option<&str> {
+                #cursor
+            }
+
+            fn ordering(&self) -> ::std::option::Option<&str> {
+                #ordering
+            }
+
+            fn condition(&self) -> ::woof::__private::sea_orm::Condition {
+                #[allow(unused_mut)]
+                let mut condition = ::woof::__private::sea_orm::Condition::all();
+                #(#conditions)*
+                condition
+            }
+        }
+    })
+}
@@ -0,0 +1,40 @@
+use proc_macro::TokenStream;
+use quote::format_ident;
+use syn::{parse_macro_input, DeriveInput};
+
+mod active_model;
+mod attributes;
+mod filter;
+mod rest;
+
+#[proc_macro_derive(Rest, attributes(woof))]
+pub fn derive_rest(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    rest::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Filter, attributes(woof))]
+pub fn derive_filter(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    filter::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Create, attributes(woof))]
+pub fn derive_create(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    active_model::derive(input, format_ident!("Create"))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Update, attributes(woof))]
+pub fn derive_update(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    active_model::derive(input, format_ident!("Update"))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,239 @@
+use crate::attributes::Attributes;
+use heck::CamelCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, DeriveInput, Expr, Ident, LitInt, LitStr, Token, Type};
+
+pub(crate) fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let attributes = Attributes::parse(&input.attrs)?;
+    attributes.allow(&[
+        "entity",
+        "repr",
+        "active_model",
+        "create",
+        "update",
+        "filter",
+        "pagination",
+        "count",
+        "max_limit",
+        "idempotent_delete",
+        "orderable",
+        "ordering",
+        "expand",
+        "expand_depth",
+        "expand_limit",
+        "hooks",
+        "permission",
+    ])?;
+
+    let ty = |name: &str, default: Type| -> syn::Result<Type> {
+        Ok(attributes.parse_value(name)?.unwrap_or(default))
+    };
+    let entity = ty("entity", parse_quote!(Entity))?;
+    let repr = ty("repr", parse_quote!(Model))?;
+    let active_model = ty("active_model", parse_quote!(ActiveModel))?;
+    let create = ty("create", parse_quote!(CreateModel))?;
+    let update = ty("update", parse_quote!(UpdateModel))?;
+    let filter = ty("filter", parse_quote!(FilterModel))?;
+    let pagination = ty(
+        "pagination",
+        parse_quote!(::woof::PageNumberPagination<#repr>),
+    )?;
+
+    let count_policy = attributes
+        .get("count")?
+        .map(|policy| {
+            let variant = match policy.value().as_str() {
+                "exact" => format_ident!("Exact"),
+                "skip" => format_ident!("Skip"),
+                "approximate" => format_ident!("Approximate"),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        policy,
+                        "expected one of \"exact\", \"skip\" or \"approximate\"",
+                    ))
+                }
+            };
+            Ok(quote! {
+                fn count_policy() -> ::woof::CountPolicy {
+                    ::woof::CountPolicy::#variant
+                }
+            })
+        })
+        .transpose()?;
+    let max_limit = attributes
+        .parse_value::<LitInt>("max_limit")?
+        .map(|max_limit| {
+            quote! {
+                fn max_limit() -> ::std::option::Option<usize> {
+                    ::std::option::Option::Some(#max_limit)
+                }
+            }
+        });
+    let idempotent_delete = attributes.has("idempotent_delete").then(|| {
+        quote! {
+            fn idempotent_delete() -> bool {
+                true
+            }
+        }
+    });
+    let column = |name: &str| {
+        let variant = format_ident!("{}", name.to_camel_case());
+        quote!(<#entity as ::woof::__private::sea_orm::EntityTrait>::Column::#variant)
+    };
+    let orderable = attributes.get("orderable")?.map(|names| {
+        let columns = split(names).map(|name| column(&name));
+        quote! {
+            fn orderable_columns() -> ::std::vec::Vec<
+                <Self::Entity as ::woof::__private::sea_orm::EntityTrait>::Column,
+            > {
+                ::std::vec![#(#columns),*]
+            }
+        }
+    });
+    let ordering = attributes.get("ordering")?.map(|names| {
+        let ordering = split(names).map(|name| match name.strip_prefix('-') {
+            Some(name) => {
+                let column = column(name);
+                quote!((#column, ::woof::__private::sea_orm::Order::Desc))
+            }
+            None => {
+                let column = column(&name);
+                quote!((#column, ::woof::__private::sea_orm::Order::Asc))
+            }
+        });
+        quote! {
+            fn default_ordering() -> ::std::vec::Vec<(
+                <Self::Entity as ::woof::__private::sea_orm::EntityTrait>::Column,
+                ::woof::__private::sea_orm::Order,
+            )> {
+                ::std::vec![#(#ordering),*]
+            }
+        }
+    });
+
+    let expansions =
+        attributes
+            .parse_value::<Expansions>("expand")?
+            .map(|Expansions(expansions)| {
+                let expansions = expansions.iter().map(|Expansion { name, ty, .. }| {
+                    let name = name.to_string();
+                    quote!(::woof::Expansion::new::<#ty>(#name))
+                });
+                quote! {
+                    fn expansions() -> ::std::vec::Vec<::woof::Expansion<Self>> {
+                        ::std::vec![#(#expansions),*]
+                    }
+                }
+            });
+    let expand_depth = attributes
+        .parse_value::<LitInt>("expand_depth")?
+        .map(|depth| {
+            quote! {
+                fn max_expansion_depth() -> usize {
+                    #depth
+                }
+            }
+        });
+    let expand_limit = attributes
+        .parse_value::<LitInt>("expand_limit")?
+        .map(|limit| {
+            quote! {
+                fn max_expanded_rows() -> u64 {
+                    #limit
+                }
+            }
+        });
+    let permission = attributes
+        .parse_value::<Expr>("permission")?
+        .map(|permission| {
+            quote! {
+                fn permission() -> ::std::boxed::Box<dyn ::woof::Permission<Self>> {
+                    ::std::boxed::Box::new(#permission)
+                }
+            }
+        });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let hooks = if attributes.has("hooks") {
+        quote!()
+    } else {
+        quote! {
+            impl #impl_generics ::woof::Hooks for #ident #ty_generics #where_clause {}
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::woof::Rest for #ident #ty_generics #where_clause {
+            type Entity = #entity;
+            type Repr = #repr;
+            type ActiveModel = #active_model;
+            type Create = #create;
+            type Update = #update;
+            type Filter = #filter;
+            type Pagination = #pagination;
+
+            fn id_from_path(
+                scope: ::std::option::Option<&str>,
+                path: &::woof::__private::actix_web::dev::Path<::woof::__private::actix_web::dev::Url>,
+            ) -> ::woof::Result<
+                <<Self::Entity as ::woof::__private::sea_orm::EntityTrait>::PrimaryKey
+                    as ::woof::__private::sea_orm::PrimaryKeyTrait>::ValueType,
+            > {
+                ::woof::id_from_path::<Self::Entity>(scope, path)
+            }
+
+            #count_policy
+            #max_limit
+            #idempotent_delete
+            #orderable
+            #ordering
+            #expansions
+            #expand_depth
+            #expand_limit
+            #permission
+        }
+
+        #hooks
+    })
+}
+
+/// Splits a comma separated list of column names, as used by `orderable` and `ordering`.
+fn split(names: &LitStr) -> impl Iterator<Item = String> {
+    names
+        .value()
+        .split(',')
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// An entry of `expand`, naming a field and the resource it expands into, such as
+/// `author: super::users::RestModel`.
+struct Expansion {
+    name: Ident,
+    _colon: Token![:],
+    ty: Type,
+}
+
+impl Parse for Expansion {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            name: input.parse()?,
+            _colon: input.parse()?,
+            ty: input.parse()?,
+        })
+    }
+}
+
+struct Expansions(Punctuated<Expansion, Token![,]>);
+
+impl Parse for Expansions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Punctuated::parse_terminated(input).map(Self)
+    }
+}